@@ -0,0 +1,294 @@
+//! An asynchronous version of [`Clock`], for use on the tokio runtime.
+//!
+//! Steering a clock is a (short) blocking system call. [`BlockingClock`] offloads those calls to
+//! tokio's blocking thread pool so they don't stall other tasks on the executor. The only
+//! operation that is truly asynchronous is waiting for PTP external timestamp events, see
+//! `unix::AsyncUnixClock` (Linux only).
+use crate::{Clock, LeapIndicator, TimeOffset, Timestamp};
+use core::{future::Future, time::Duration};
+use std::sync::Arc;
+
+/// Asynchronous counterpart of [`Clock`]. See there for the meaning of each method.
+pub trait AsyncClock {
+    type Error: std::error::Error;
+
+    /// Get the current time.
+    fn now(&self) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+
+    /// Get the clock's resolution.
+    fn resolution(&self) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+
+    /// Change the frequency of the clock.
+    /// Returns the time at which the change was applied.
+    fn set_frequency(
+        &self,
+        frequency: f64,
+    ) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+
+    /// Get the frequency of the clock
+    fn get_frequency(&self) -> impl Future<Output = Result<f64, Self::Error>> + Send;
+
+    /// Change the current time of the clock by an offset.
+    /// Returns the time at which the change was applied.
+    fn step_clock(
+        &self,
+        offset: TimeOffset,
+    ) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+
+    /// Change the indicators for upcoming leap seconds.
+    /// Returns the time at which the change was applied.
+    fn set_leap_seconds(
+        &self,
+        leap_status: LeapIndicator,
+    ) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+
+    /// Disable all standard NTP kernel clock discipline.
+    fn disable_kernel_ntp_algorithm(&self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Set the offset between TAI and UTC.
+    fn set_tai(&self, tai_offset: i32) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Get the offset between TAI and UTC.
+    fn get_tai(&self) -> impl Future<Output = Result<i32, Self::Error>> + Send;
+
+    /// Provide the system with the current best estimates for the statistical
+    /// error of the clock.
+    /// Returns the time at which the change was applied.
+    fn error_estimate_update(
+        &self,
+        estimated_error: Duration,
+        maximum_error: Duration,
+    ) -> impl Future<Output = Result<Timestamp, Self::Error>> + Send;
+}
+
+/// Adapts a [`Clock`] into an [`AsyncClock`] by running every operation on
+/// [`tokio::task::spawn_blocking`].
+///
+/// ```no_run
+/// use clock_steering::{async_clock::{AsyncClock, BlockingClock}, unix::UnixClock};
+///
+/// # async fn example() -> Result<(), clock_steering::unix::Error> {
+/// let clock = BlockingClock::new(UnixClock::CLOCK_REALTIME);
+/// let now = clock.now().await?;
+///
+/// println!("{now:?}");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// A panic of the wrapped clock is resumed in the task that awaits the operation. The operations
+/// also panic when the runtime shuts down before they ran, because then there is no result of
+/// the clock to return.
+#[derive(Debug)]
+pub struct BlockingClock<C> {
+    clock: Arc<C>,
+}
+
+impl<C> Clone for BlockingClock<C> {
+    fn clone(&self) -> Self {
+        Self {
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<C: Clock + Send + Sync + 'static> BlockingClock<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock: Arc::new(clock),
+        }
+    }
+
+    /// The wrapped clock, for calling its methods directly
+    pub fn get_ref(&self) -> &C {
+        &self.clock
+    }
+
+    async fn run<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&C) -> T + Send + 'static,
+    {
+        let clock = self.clock.clone();
+
+        match tokio::task::spawn_blocking(move || f(&clock)).await {
+            Ok(value) => value,
+            Err(error) => match error.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                // only when the runtime shuts down before the operation ran
+                Err(error) => panic!("clock operation did not complete: {error}"),
+            },
+        }
+    }
+}
+
+impl<C> AsyncClock for BlockingClock<C>
+where
+    C: Clock + Send + Sync + 'static,
+    C::Error: std::error::Error + Send + 'static,
+{
+    type Error = C::Error;
+
+    /// Get the current time.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn now(&self) -> Result<Timestamp, Self::Error> {
+        self.run(|clock| clock.now()).await
+    }
+
+    /// Get the clock's resolution.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn resolution(&self) -> Result<Timestamp, Self::Error> {
+        self.run(|clock| clock.resolution()).await
+    }
+
+    /// Change the frequency of the clock.
+    /// Returns the time at which the change was applied.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
+        self.run(move |clock| clock.set_frequency(frequency)).await
+    }
+
+    /// Get the frequency of the clock
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn get_frequency(&self) -> Result<f64, Self::Error> {
+        self.run(|clock| clock.get_frequency()).await
+    }
+
+    /// Change the current time of the clock by an offset.
+    /// Returns the time at which the change was applied.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+        self.run(move |clock| clock.step_clock(offset)).await
+    }
+
+    /// Change the indicators for upcoming leap seconds.
+    /// Returns the time at which the change was applied.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+        self.run(move |clock| clock.set_leap_seconds(leap_status))
+            .await
+    }
+
+    /// Disable all standard NTP kernel clock discipline.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+        self.run(|clock| clock.disable_kernel_ntp_algorithm()).await
+    }
+
+    /// Set the offset between TAI and UTC.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn set_tai(&self, tai_offset: i32) -> Result<(), Self::Error> {
+        self.run(move |clock| clock.set_tai(tai_offset)).await
+    }
+
+    /// Get the offset between TAI and UTC.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn get_tai(&self) -> Result<i32, Self::Error> {
+        self.run(|clock| clock.get_tai()).await
+    }
+
+    /// Provide the system with the current best estimates for the statistical
+    /// error of the clock.
+    /// Returns the time at which the change was applied.
+    ///
+    /// # Panics
+    ///
+    /// When the wrapped clock panics, or the runtime shuts down, see [`BlockingClock`](BlockingClock#panics).
+    async fn error_estimate_update(
+        &self,
+        estimated_error: Duration,
+        maximum_error: Duration,
+    ) -> Result<Timestamp, Self::Error> {
+        self.run(move |clock| clock.error_estimate_update(estimated_error, maximum_error))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_clock::{MockClock, Operation};
+
+    // without `#[tokio::test]`, tokio is not a dev-dependency, which would be built on the MSRV too
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("failed to build a runtime")
+            .block_on(future)
+    }
+
+    #[test]
+    fn blocking_clock_forwards_operations() {
+        block_on(blocking_clock_forwards_operations_async())
+    }
+
+    async fn blocking_clock_forwards_operations_async() {
+        let clock = BlockingClock::new(MockClock::new(Timestamp::default()));
+
+        clock.set_frequency(25.0).await.unwrap();
+        clock.set_tai(37).await.unwrap();
+
+        let offset = TimeOffset {
+            seconds: 5,
+            nanos: 0,
+        };
+        let applied = clock.step_clock(offset).await.unwrap();
+
+        assert_eq!(
+            applied,
+            Timestamp {
+                seconds: 5,
+                nanos: 0,
+            }
+        );
+        assert_eq!(clock.now().await.unwrap(), applied);
+        assert_eq!(clock.get_frequency().await.unwrap(), 25.0);
+        assert_eq!(clock.get_tai().await.unwrap(), 37);
+
+        assert_eq!(
+            clock.get_ref().history(),
+            vec![
+                Operation::SetFrequency(25.0),
+                Operation::SetTai(37),
+                Operation::StepClock(offset),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "mock clock failure")]
+    fn blocking_clock_propagates_panics() {
+        let clock = BlockingClock::new(MockClock::new(Timestamp::default()));
+
+        block_on(clock.run(|_| -> () { panic!("mock clock failure") }));
+    }
+}
@@ -0,0 +1,163 @@
+//! Control loops that turn offset measurements into clock corrections.
+//!
+//! Nothing in here touches a clock: feed the output into [`Clock::set_frequency`](crate::Clock::set_frequency).
+use crate::SignedDuration;
+use core::time::Duration;
+
+/// The frequency limit of the kernel NTP interface, in parts per million
+const KERNEL_MAX_FREQUENCY: f64 = 500.0;
+
+/// A proportional-integral controller for the frequency of a clock.
+///
+/// The offset passed to [`PiController::update`] is the time of the reference minus the time of
+/// the clock, so a positive offset means the clock is behind and must run faster.
+///
+/// ```
+/// use clock_steering::{discipline::PiController, SignedDuration};
+/// use std::time::Duration;
+///
+/// let mut controller = PiController::new(0.3e6, 0.03e6);
+///
+/// let offset = SignedDuration {
+///     negative: false,
+///     duration: Duration::from_micros(10),
+/// };
+/// let frequency = controller.update(offset, Duration::from_secs(1));
+///
+/// assert!(frequency > 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PiController {
+    /// Frequency correction (in ppm) per second of offset
+    pub proportional_gain: f64,
+    /// Growth of the integral term (in ppm per second) per second of offset
+    pub integral_gain: f64,
+    max_frequency: f64,
+    integral: f64,
+}
+
+impl PiController {
+    /// A controller with the given gains, limited to the ±500 ppm the kernel accepts
+    pub fn new(proportional_gain: f64, integral_gain: f64) -> Self {
+        Self {
+            proportional_gain,
+            integral_gain,
+            max_frequency: KERNEL_MAX_FREQUENCY,
+            integral: 0.0,
+        }
+    }
+
+    /// Limit the frequency correction to `max_frequency` (in ppm) in either direction. The
+    /// integral term is clamped to this limit as well, so it does not wind up while the output
+    /// saturates. The sign of `max_frequency` is ignored.
+    ///
+    /// # Panics
+    ///
+    /// When `max_frequency` is NaN.
+    pub fn with_max_frequency(mut self, max_frequency: f64) -> Self {
+        assert!(!max_frequency.is_nan(), "the frequency limit is NaN");
+
+        self.max_frequency = max_frequency.abs();
+        self
+    }
+
+    /// The limit of the frequency correction (in ppm), see [`PiController::with_max_frequency`]
+    pub fn max_frequency(&self) -> f64 {
+        self.max_frequency
+    }
+
+    /// Process an offset measured `interval` after the previous one, and return the frequency
+    /// (in ppm) to apply with [`Clock::set_frequency`](crate::Clock::set_frequency).
+    pub fn update(&mut self, offset: SignedDuration, interval: Duration) -> f64 {
+        let offset = offset.as_secs_f64();
+
+        self.integral += self.integral_gain * offset * interval.as_secs_f64();
+        self.integral = self.integral.clamp(-self.max_frequency, self.max_frequency);
+
+        let frequency = self.proportional_gain * offset + self.integral;
+        frequency.clamp(-self.max_frequency, self.max_frequency)
+    }
+
+    /// The integral term, which converges to the frequency error of the clock
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Forget the accumulated integral term, e.g. after stepping the clock
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(seconds: f64) -> SignedDuration {
+        SignedDuration {
+            negative: seconds < 0.0,
+            duration: Duration::from_secs_f64(seconds.abs()),
+        }
+    }
+
+    #[test]
+    fn integral_cancels_constant_drift() {
+        let mut controller = PiController::new(0.3e6, 0.03e6);
+        let interval = Duration::from_secs(1);
+
+        // the clock runs 50 ppm fast, and starts 1ms ahead
+        let drift = 50.0;
+        let mut offset = -1e-3;
+        let mut frequency = 0.0;
+
+        for _ in 0..500 {
+            frequency = controller.update(signed(offset), interval);
+            offset -= (drift + frequency) * 1e-6 * interval.as_secs_f64();
+        }
+
+        assert!((controller.integral() + drift).abs() < 1e-3);
+        assert!((frequency + drift).abs() < 1e-3);
+        assert!(offset.abs() < 1e-9);
+    }
+
+    #[test]
+    fn integral_does_not_wind_up() {
+        let mut controller = PiController::new(0.3e6, 0.03e6);
+
+        // an offset the frequency correction cannot keep up with
+        for _ in 0..1000 {
+            let frequency = controller.update(signed(1.0), Duration::from_secs(1));
+            assert_eq!(frequency, KERNEL_MAX_FREQUENCY);
+        }
+
+        assert_eq!(controller.integral(), KERNEL_MAX_FREQUENCY);
+
+        // the integral recovers as soon as the offset changes sign
+        let frequency = controller.update(signed(-1e-3), Duration::from_secs(1));
+        assert!(frequency < KERNEL_MAX_FREQUENCY);
+
+        controller.reset();
+        assert_eq!(controller.integral(), 0.0);
+    }
+
+    #[test]
+    fn max_frequency_is_a_magnitude() {
+        let mut controller = PiController::new(0.3e6, 0.03e6).with_max_frequency(-100.0);
+        assert_eq!(controller.max_frequency(), 100.0);
+
+        assert_eq!(
+            controller.update(signed(1.0), Duration::from_secs(1)),
+            100.0
+        );
+        assert_eq!(
+            controller.update(signed(-1.0), Duration::from_secs(1)),
+            -100.0
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN")]
+    fn max_frequency_rejects_nan() {
+        let _ = PiController::new(0.3e6, 0.03e6).with_max_frequency(f64::NAN);
+    }
+}
@@ -0,0 +1,122 @@
+//! Growth of the dispersion of a clock between updates.
+//!
+//! Between measurements, the maximum error of a clock grows with its worst case frequency error.
+//! Pass the grown dispersion as the maximum error to
+//! [`Clock::error_estimate_update`].
+use crate::{Clock, Timestamp};
+use core::time::Duration;
+
+/// The rate at which the dispersion grows, in parts per million (PHI in RFC 5905)
+pub const GROWTH_RATE_PPM: u64 = 15;
+
+/// The dispersion `elapsed` after it was `base`
+pub fn grow(base: Duration, elapsed: Duration) -> Duration {
+    let growth = elapsed.as_nanos() * u128::from(GROWTH_RATE_PPM) / 1_000_000;
+    let growth = Duration::from_nanos(growth.try_into().unwrap_or(u64::MAX));
+
+    base.saturating_add(growth)
+}
+
+/// Tracks the dispersion since the last update, using the time of a (preferably monotonic)
+/// clock.
+///
+#[cfg_attr(all(unix, feature = "std"), doc = "```no_run")]
+#[cfg_attr(not(all(unix, feature = "std")), doc = "```ignore")]
+/// use clock_steering::{dispersion::DispersionAccumulator, unix::UnixClock, Clock};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let clock = UnixClock::CLOCK_REALTIME;
+///     let dispersion =
+///         DispersionAccumulator::new(UnixClock::CLOCK_MONOTONIC, Duration::from_millis(1))?;
+///
+///     loop {
+///         std::thread::sleep(Duration::from_secs(16));
+///
+///         let maximum_error = dispersion.current()?;
+///         clock.error_estimate_update(Duration::from_micros(100), maximum_error)?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DispersionAccumulator<C> {
+    clock: C,
+    base: Duration,
+    last_update: Timestamp,
+}
+
+impl<C: Clock> DispersionAccumulator<C> {
+    /// Start tracking from a dispersion of `base` at the current time of `clock`
+    pub fn new(clock: C, base: Duration) -> Result<Self, C::Error> {
+        let last_update = clock.now()?;
+
+        Ok(Self {
+            clock,
+            base,
+            last_update,
+        })
+    }
+
+    /// Reset the dispersion to `base`, e.g. after a new measurement
+    pub fn update(&mut self, base: Duration) -> Result<(), C::Error> {
+        self.last_update = self.clock.now()?;
+        self.base = base;
+
+        Ok(())
+    }
+
+    /// The dispersion at the current time of the clock
+    pub fn current(&self) -> Result<Duration, C::Error> {
+        let now = self.clock.now()?;
+        let elapsed = now.duration_since(&self.last_update).unwrap_or_default();
+
+        Ok(grow(self.base, elapsed))
+    }
+
+    /// The clock whose time the dispersion grows with
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::test_clock::MockClock;
+
+    #[test]
+    fn grows_linearly() {
+        let base = Duration::from_micros(100);
+
+        assert_eq!(grow(base, Duration::ZERO), base);
+        assert_eq!(
+            grow(base, Duration::from_secs(1)),
+            base + Duration::from_micros(15)
+        );
+        assert_eq!(
+            grow(base, Duration::from_secs(64)),
+            base + Duration::from_micros(960)
+        );
+        assert_eq!(grow(Duration::MAX, Duration::MAX), Duration::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn accumulator_restarts_on_update() {
+        let clock = MockClock::new(Timestamp::default());
+        let mut accumulator = DispersionAccumulator::new(clock, Duration::ZERO).unwrap();
+
+        accumulator.clock().advance(Duration::from_secs(100));
+        assert_eq!(accumulator.current().unwrap(), Duration::from_micros(1_500));
+
+        accumulator.clock().advance(Duration::from_secs(100));
+        assert_eq!(accumulator.current().unwrap(), Duration::from_micros(3_000));
+
+        accumulator.update(Duration::from_millis(1)).unwrap();
+        assert_eq!(accumulator.current().unwrap(), Duration::from_millis(1));
+
+        accumulator.clock().advance(Duration::from_secs(100));
+        assert_eq!(accumulator.current().unwrap(), Duration::from_micros(2_500));
+    }
+}
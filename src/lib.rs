@@ -1,31 +1,629 @@
 //! Logic for steering OS clocks, aimed at NTP and PTP.
 //!
 //! This code is used in our implementations of NTP [ntpd-rs](https://github.com/pendulum-project/ntpd-rs) and PTP [statime](https://github.com/pendulum-project/statime).
-use core::time::Duration;
+//!
+//! Without the default `std` feature, the crate is `no_std`: only the [`Clock`] trait, the time
+//! types and the control loops remain, so that [`Clock`] can be implemented for custom hardware.
+#![cfg_attr(not(feature = "std"), no_std)]
+use core::{
+    ops::{Add, Neg, Sub},
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Log a steering operation with its result at debug level, when the `log` feature is enabled.
+/// Evaluates to the result.
+#[cfg(all(unix, feature = "std"))]
+macro_rules! log_steering {
+    ($result:expr, $($operation:tt)+) => {{
+        let result = $result;
 
-#[cfg(unix)]
+        #[cfg(feature = "log")]
+        log::debug!("{} = {:?}", format_args!($($operation)+), result);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($operation)+);
+
+        result
+    }};
+}
+
+#[cfg(feature = "tokio")]
+pub mod async_clock;
+pub mod discipline;
+pub mod dispersion;
+#[cfg(feature = "std")]
+pub mod synced;
+pub mod synchronizer;
+#[cfg(all(feature = "std", any(test, feature = "test-util")))]
+pub mod test_clock;
+pub mod units;
+#[cfg(all(unix, feature = "std"))]
 pub mod unix;
+#[cfg(all(windows, feature = "std"))]
+pub mod windows;
+
+pub use synchronizer::align_clock;
+
+/// The type of the seconds of a [`Timestamp`] and [`TimeOffset`]: [`libc::time_t`] on platforms
+/// that have one, and 64 bits elsewhere (e.g. embedded targets without an operating system).
+#[cfg(any(unix, windows))]
+#[allow(non_camel_case_types)]
+pub type time_t = libc::time_t;
+/// The type of the seconds of a [`Timestamp`] and [`TimeOffset`]: `libc::time_t` on platforms
+/// that have one, and 64 bits elsewhere (e.g. embedded targets without an operating system).
+#[cfg(not(any(unix, windows)))]
+#[allow(non_camel_case_types)]
+pub type time_t = i64;
 
 /// A moment in time.
 ///
-/// The format makes it easy to convert into libc data structures, and supports subnanoseconds that
-/// certain hardware can provide for additional precision. The value is an offset from the [unix epoch](https://en.wikipedia.org/wiki/Unix_time).
+/// The format makes it easy to convert into libc data structures. The precision is limited to
+/// nanoseconds, the smallest unit the kernel interfaces accept. The value is an offset from the [unix epoch](https://en.wikipedia.org/wiki/Unix_time).
+///
+/// Comparisons are field by field, so a timestamp with `nanos` of a second or more only compares
+/// as expected after [`Timestamp::normalized`]. Timestamps produced by this crate are always
+/// normalized.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SecondsNanos"))]
 pub struct Timestamp {
-    pub seconds: libc::time_t,
+    /// Where [`libc::time_t`] is 32 bits wide (most 32-bit platforms), this runs out in
+    /// January 2038. Use [`Timestamp::try_from_seconds_nanos`] to convert from a 64-bit value.
+    pub seconds: time_t,
     /// Nanos must be between 0 and 999999999 inclusive
     pub nanos: u32,
 }
 
+impl Timestamp {
+    const NANOS_PER_SECOND: u32 = 1_000_000_000;
+
+    /// Carry any whole seconds in `nanos` into `seconds`, so that `nanos` is below one second.
+    pub fn normalized(self) -> Timestamp {
+        Timestamp {
+            seconds: self
+                .seconds
+                .wrapping_add((self.nanos / Self::NANOS_PER_SECOND) as _),
+            nanos: self.nanos % Self::NANOS_PER_SECOND,
+        }
+    }
+
+    /// The number of nanoseconds since the unix epoch, negative for timestamps before it.
+    ///
+    /// This is exact, and covers the full range of [`libc::time_t`].
+    pub fn as_nanos_since_epoch(&self) -> i128 {
+        i128::from(self.seconds) * i128::from(Self::NANOS_PER_SECOND) + i128::from(self.nanos)
+    }
+
+    /// Construct a timestamp from a number of nanoseconds since the unix epoch, negative for
+    /// timestamps before it.
+    ///
+    /// # Panics
+    ///
+    /// When the seconds do not fit in a [`libc::time_t`].
+    pub fn from_nanos_since_epoch(nanos: i128) -> Timestamp {
+        let nanos_per_second = i128::from(Self::NANOS_PER_SECOND);
+
+        Timestamp {
+            seconds: nanos
+                .div_euclid(nanos_per_second)
+                .try_into()
+                .expect("timestamp out of range for time_t"),
+            nanos: nanos.rem_euclid(nanos_per_second) as u32,
+        }
+    }
+
+    /// Construct a timestamp from 64-bit seconds, failing when they do not fit in a
+    /// [`libc::time_t`] (instead of wrapping to 1901 after 2038 on 32-bit platforms), or when
+    /// `nanos` is a second or more.
+    pub fn try_from_seconds_nanos(
+        seconds: i64,
+        nanos: u32,
+    ) -> Result<Timestamp, TimestampRangeError> {
+        if nanos >= Self::NANOS_PER_SECOND {
+            return Err(TimestampRangeError);
+        }
+
+        // time_t is 32 bits wide on some platforms
+        #[allow(clippy::useless_conversion)]
+        let seconds = seconds.try_into().map_err(|_| TimestampRangeError)?;
+
+        Ok(Timestamp { seconds, nanos })
+    }
+
+    /// Add a [`Duration`] to this timestamp, returning `None` when the result does not fit in a
+    /// [`libc::time_t`].
+    pub fn checked_add(self, duration: Duration) -> Option<Timestamp> {
+        let mut seconds = self
+            .seconds
+            .checked_add(time_t::try_from(duration.as_secs()).ok()?)?;
+
+        // both values are below 1e9, so this cannot overflow a u32
+        let mut nanos = self.nanos + duration.subsec_nanos();
+
+        if nanos >= Self::NANOS_PER_SECOND {
+            seconds = seconds.checked_add(1)?;
+            nanos -= Self::NANOS_PER_SECOND;
+        }
+
+        Some(Timestamp { seconds, nanos })
+    }
+
+    /// Subtract a [`Duration`] from this timestamp, returning `None` when the result does not fit
+    /// in a [`libc::time_t`].
+    pub fn checked_sub(self, duration: Duration) -> Option<Timestamp> {
+        let mut seconds = self
+            .seconds
+            .checked_sub(time_t::try_from(duration.as_secs()).ok()?)?;
+
+        let nanos = match self.nanos.checked_sub(duration.subsec_nanos()) {
+            Some(nanos) => nanos,
+            None => {
+                seconds = seconds.checked_sub(1)?;
+                self.nanos + Self::NANOS_PER_SECOND - duration.subsec_nanos()
+            }
+        };
+
+        Some(Timestamp { seconds, nanos })
+    }
+
+    /// The amount of time elapsed from `earlier` to `self`, or `None` if `earlier` is later than
+    /// `self`.
+    pub fn duration_since(&self, earlier: &Timestamp) -> Option<Duration> {
+        if earlier > self {
+            return None;
+        }
+
+        // time_t may be 32 bits wide on some platforms
+        let mut seconds: u64 = self.seconds.abs_diff(earlier.seconds) as _;
+
+        let nanos = match self.nanos.checked_sub(earlier.nanos) {
+            Some(nanos) => nanos,
+            None => {
+                // self is later, so there is at least one whole second to borrow from
+                seconds -= 1;
+                self.nanos + Self::NANOS_PER_SECOND - earlier.nanos
+            }
+        };
+
+        Some(Duration::new(seconds, nanos))
+    }
+
+    /// The signed amount of time from `self` to `other`. The result is negative when `other` is
+    /// earlier than `self`.
+    pub fn offset_to(&self, other: &Timestamp) -> SignedDuration {
+        match other.duration_since(self) {
+            Some(duration) => SignedDuration {
+                negative: false,
+                duration,
+            },
+            None => SignedDuration {
+                negative: true,
+                duration: self
+                    .duration_since(other)
+                    .expect("other is earlier than self"),
+            },
+        }
+    }
+
+    /// Round this timestamp to a multiple of `resolution` since the unix epoch, e.g. to compare
+    /// it with the time of a clock with a coarser resolution. A zero resolution leaves the
+    /// timestamp unchanged.
+    ///
+    /// # Panics
+    ///
+    /// When the result is beyond the range of [`libc::time_t`]: rounding up (with
+    /// [`RoundMode::Up`] or [`RoundMode::Nearest`]) close to its maximum, or rounding down (with
+    /// [`RoundMode::Down`] or [`RoundMode::Nearest`]) close to its minimum. See
+    /// [`Timestamp::checked_round_to`] for a version without panic.
+    pub fn round_to(&self, resolution: Duration, mode: RoundMode) -> Timestamp {
+        self.checked_round_to(resolution, mode)
+            .expect("rounded timestamp out of range for time_t")
+    }
+
+    /// Round this timestamp like [`Timestamp::round_to`], returning `None` when the result does
+    /// not fit in a [`libc::time_t`].
+    pub fn checked_round_to(&self, resolution: Duration, mode: RoundMode) -> Option<Timestamp> {
+        // at most u64::MAX seconds, which easily fits
+        let resolution =
+            i128::try_from(resolution.as_nanos()).expect("a Duration fits in i128 nanoseconds");
+        if resolution == 0 {
+            return Some(*self);
+        }
+
+        let nanos = self.as_nanos_since_epoch();
+        let remainder = nanos.rem_euclid(resolution);
+        let down = nanos - remainder;
+
+        let rounded = match mode {
+            RoundMode::Down => down,
+            RoundMode::Up if remainder == 0 => down,
+            RoundMode::Up => down + resolution,
+            RoundMode::Nearest if remainder * 2 >= resolution => down + resolution,
+            RoundMode::Nearest => down,
+        };
+
+        let nanos_per_second = i128::from(Self::NANOS_PER_SECOND);
+
+        Some(Timestamp {
+            seconds: rounded.div_euclid(nanos_per_second).try_into().ok()?,
+            nanos: rounded.rem_euclid(nanos_per_second) as u32,
+        })
+    }
+}
+
+/// How [`Timestamp::round_to`] rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundMode {
+    /// To the closest multiple, rounding up halfway between two multiples
+    Nearest,
+    /// To the closest earlier (or equal) multiple
+    Down,
+    /// To the closest later (or equal) multiple
+    Up,
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// # Panics
+    ///
+    /// This function may panic if the resulting point in time cannot be represented by the
+    /// underlying data structure. See [`Timestamp::checked_add`] for a version without panic.
+    fn add(self, rhs: Duration) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to timestamp")
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// # Panics
+    ///
+    /// This function may panic if the resulting point in time cannot be represented by the
+    /// underlying data structure. See [`Timestamp::checked_sub`] for a version without panic.
+    fn sub(self, rhs: Duration) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from timestamp")
+    }
+}
+
+impl core::fmt::Display for Timestamp {
+    /// Formats the timestamp as an RFC 3339 UTC time, e.g. `2024-02-16T12:34:56.123456789Z`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+        // time_t may be 32 bits wide on some platforms
+        let seconds: i64 = self.seconds as _;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        let seconds_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+            year,
+            month,
+            day,
+            seconds_of_day / 3600,
+            (seconds_of_day / 60) % 60,
+            seconds_of_day % 60,
+            self.nanos
+        )
+    }
+}
+
+/// Convert a number of days since the unix epoch into a (year, month, day) in the proleptic
+/// Gregorian calendar.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // shift the epoch to 0000-03-01, so leap days fall at the end of a (400-year) era
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months counted from march
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = FromSystemTimeError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let duration = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| FromSystemTimeError::BeforeEpoch)?;
+
+        Timestamp::default()
+            .checked_add(duration)
+            .ok_or(FromSystemTimeError::OutOfRange)
+    }
+}
+
+/// Fails for timestamps outside the range of [`SystemTime`], which depends on the platform.
+#[cfg(feature = "std")]
+impl TryFrom<Timestamp> for SystemTime {
+    type Error = TimestampRangeError;
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, Self::Error> {
+        let offset = Timestamp::default().offset_to(&timestamp);
+
+        match offset.negative {
+            false => UNIX_EPOCH.checked_add(offset.duration),
+            true => UNIX_EPOCH.checked_sub(offset.duration),
+        }
+        .ok_or(TimestampRangeError)
+    }
+}
+
+/// Fails for timestamps outside the range of [`time::OffsetDateTime`], which by default covers
+/// the years -9999 up to 9999.
+#[cfg(feature = "time")]
+impl TryFrom<Timestamp> for time::OffsetDateTime {
+    type Error = time::error::ComponentRange;
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, Self::Error> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(timestamp.as_nanos_since_epoch())
+    }
+}
+
+/// Fails for times that do not fit in a [`libc::time_t`]. The time is converted to UTC.
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for Timestamp {
+    type Error = TimestampRangeError;
+
+    fn try_from(time: time::OffsetDateTime) -> Result<Self, Self::Error> {
+        Timestamp::try_from_seconds_nanos(time.unix_timestamp(), time.nanosecond())
+    }
+}
+
+/// Fails for timestamps outside the range of [`chrono::DateTime`], which covers about 262,000
+/// years around the common era.
+#[cfg(feature = "chrono")]
+impl TryFrom<Timestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = TimestampRangeError;
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, Self::Error> {
+        // normalized, because chrono uses nanos beyond a second for leap seconds
+        let timestamp = timestamp.normalized();
+
+        // time_t may be 32 bits wide on some platforms
+        #[allow(clippy::useless_conversion)]
+        let seconds = timestamp.seconds.into();
+
+        chrono::DateTime::from_timestamp(seconds, timestamp.nanos).ok_or(TimestampRangeError)
+    }
+}
+
+/// Fails for times that do not fit in a [`libc::time_t`]. A leap second is folded into the
+/// second before it, like the kernel does.
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for Timestamp {
+    type Error = TimestampRangeError;
+
+    fn try_from(time: chrono::DateTime<chrono::Utc>) -> Result<Self, Self::Error> {
+        let nanos = time.timestamp_subsec_nanos() % Timestamp::NANOS_PER_SECOND;
+        Timestamp::try_from_seconds_nanos(time.timestamp(), nanos)
+    }
+}
+
+/// Errors that can occur when converting a `SystemTime` into a [`Timestamp`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FromSystemTimeError {
+    /// The time lies before the unix epoch.
+    BeforeEpoch,
+    /// The time does not fit in a [`libc::time_t`].
+    OutOfRange,
+}
+
+impl core::fmt::Display for FromSystemTimeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let msg = match self {
+            Self::BeforeEpoch => "The time lies before the unix epoch.",
+            Self::OutOfRange => "The time does not fit in a time_t.",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromSystemTimeError {}
+
+/// The error of [`Timestamp::try_from_seconds_nanos`] and of the conversions into a [`Timestamp`]
+/// from other date and time types: the seconds do not fit in a [`libc::time_t`], or the
+/// nanoseconds are a second or more. Also the error of the conversions from a [`Timestamp`] into
+/// types with a smaller range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimestampRangeError;
+
+impl core::fmt::Display for TimestampRangeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("The timestamp does not fit in a time_t and nanoseconds.")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimestampRangeError {}
+
+/// The fields of a [`Timestamp`] or [`TimeOffset`], deserialized before `nanos` is checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SecondsNanos {
+    seconds: time_t,
+    nanos: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SecondsNanos> for Timestamp {
+    type Error = TimestampRangeError;
+
+    fn try_from(value: SecondsNanos) -> Result<Self, Self::Error> {
+        if value.nanos >= Self::NANOS_PER_SECOND {
+            return Err(TimestampRangeError);
+        }
+
+        Ok(Timestamp {
+            seconds: value.seconds,
+            nanos: value.nanos,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SecondsNanos> for TimeOffset {
+    type Error = TimestampRangeError;
+
+    fn try_from(value: SecondsNanos) -> Result<Self, Self::Error> {
+        let Timestamp { seconds, nanos } = Timestamp::try_from(value)?;
+
+        Ok(TimeOffset { seconds, nanos })
+    }
+}
+
+/// A span of time that can be negative.
+///
+/// Produced by [`Timestamp::offset_to`]. A zero duration is never negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SignedDuration {
+    /// Whether the duration points backwards in time
+    pub negative: bool,
+    /// The magnitude of the duration
+    pub duration: Duration,
+}
+
+impl SignedDuration {
+    /// The duration as (possibly negative) seconds
+    pub fn as_secs_f64(&self) -> f64 {
+        match self.negative {
+            false => self.duration.as_secs_f64(),
+            true => -self.duration.as_secs_f64(),
+        }
+    }
+}
+
+/// A signed offset to apply to a clock. Negative offsets have negative `seconds` and positive
+/// `nanos`, see [`TimeOffset::from_duration_signed`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SecondsNanos"))]
 pub struct TimeOffset {
-    pub seconds: libc::time_t,
+    pub seconds: time_t,
     /// Nanos must be between 0 and 999999999 inclusive
     pub nanos: u32,
 }
 
+impl TimeOffset {
+    const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+    /// The offset that leaves a clock unchanged
+    pub const fn zero() -> TimeOffset {
+        TimeOffset {
+            seconds: 0,
+            nanos: 0,
+        }
+    }
+
+    /// Construct an offset of `nanos` nanoseconds, which may be negative. Like
+    /// [`TimeOffset::from_duration_signed`], the seconds wrap when they do not fit in a 32-bit
+    /// [`libc::time_t`].
+    pub fn from_nanos(nanos: i64) -> TimeOffset {
+        TimeOffset {
+            seconds: nanos.div_euclid(Self::NANOS_PER_SECOND) as _,
+            nanos: nanos.rem_euclid(Self::NANOS_PER_SECOND) as _,
+        }
+    }
+
+    /// Construct an offset of `secs` seconds plus `nanos` nanoseconds, where both may be
+    /// negative. The result is normalized so that `nanos` is positive, e.g. -0.5 seconds is
+    /// represented as `seconds: -1, nanos: 500_000_000`, which is what the kernel expects.
+    ///
+    /// On platforms with a 32-bit [`libc::time_t`], the seconds wrap when they do not fit.
+    ///
+    /// # Panics
+    ///
+    /// When carrying the whole seconds of `nanos` overflows `secs`, which only happens within
+    /// a few seconds of `i64::MIN` or `i64::MAX`.
+    pub fn from_duration_signed(secs: i64, nanos: i32) -> TimeOffset {
+        let nanos = i64::from(nanos);
+
+        let seconds = secs
+            .checked_add(nanos.div_euclid(Self::NANOS_PER_SECOND))
+            .expect("offset out of range for i64 seconds");
+        let nanos = nanos.rem_euclid(Self::NANOS_PER_SECOND);
+
+        TimeOffset {
+            seconds: seconds as _,
+            nanos: nanos as _,
+        }
+    }
+
+    /// The offset of the same size in the opposite direction.
+    ///
+    /// # Panics
+    ///
+    /// When the offset is a whole number of seconds equal to `time_t::MIN`, whose negation does
+    /// not fit in a [`libc::time_t`]. See [`TimeOffset::checked_neg`].
+    pub fn negate(self) -> TimeOffset {
+        self.checked_neg()
+            .expect("negated offset out of range for time_t")
+    }
+
+    /// The offset of the same size in the opposite direction, or `None` when it does not fit in
+    /// a [`libc::time_t`].
+    pub fn checked_neg(self) -> Option<TimeOffset> {
+        match self.nanos {
+            0 => Some(TimeOffset {
+                seconds: self.seconds.checked_neg()?,
+                nanos: 0,
+            }),
+            nanos => Some(TimeOffset {
+                // -1 - seconds never overflows, also not for time_t::MIN and time_t::MAX
+                seconds: -1 - self.seconds,
+                nanos: Timestamp::NANOS_PER_SECOND - nanos,
+            }),
+        }
+    }
+}
+
+impl Neg for TimeOffset {
+    type Output = TimeOffset;
+
+    /// See [`TimeOffset::negate`], which panics in the same case.
+    fn neg(self) -> TimeOffset {
+        self.negate()
+    }
+}
+
+impl TryFrom<Duration> for TimeOffset {
+    type Error = core::num::TryFromIntError;
+
+    /// A positive offset, failing when the seconds do not fit in a [`libc::time_t`].
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        Ok(TimeOffset {
+            seconds: duration.as_secs().try_into()?,
+            nanos: duration.subsec_nanos(),
+        })
+    }
+}
+
 /// Indicate whether a leap second must be applied
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LeapIndicator {
     /// No leap second warning
     #[default]
@@ -40,10 +638,10 @@ pub enum LeapIndicator {
 
 /// Trait for reading information from and modifying an OS clock
 pub trait Clock {
-    type Error: std::error::Error;
-
-    // feature(error_in_core) https://github.com/rust-lang/rust/issues/103765
-    // type Error: core::error::Error;
+    // not `std::error::Error`, so the bound does not depend on the std feature, and
+    // core::error::Error is only stable since Rust 1.81, above our minimum supported version.
+    // All errors of this crate do implement `std::error::Error` when std is enabled.
+    type Error: core::fmt::Debug + core::fmt::Display;
 
     /// Get the current time.
     fn now(&self) -> Result<Timestamp, Self::Error>;
@@ -54,16 +652,30 @@ pub trait Clock {
     /// unavailable.
     fn resolution(&self) -> Result<Timestamp, Self::Error>;
 
+    /// Get the clock's resolution as a [`Duration`].
+    ///
+    /// The output will be zero when the resolution is unavailable.
+    fn resolution_duration(&self) -> Result<Duration, Self::Error> {
+        let resolution = self.resolution()?;
+
+        Ok(resolution
+            .duration_since(&Timestamp::default())
+            .unwrap_or_default())
+    }
+
     /// Change the frequency of the clock.
     /// Returns the time at which the change was applied.
     ///
-    /// The unit of the input is milliseconds (of drift) per second,
-    /// compared to the "natural" frequency of the clock.
+    /// The unit of the input is parts per million, i.e. microseconds (of drift) per second,
+    /// compared to the "natural" frequency of the clock. See [`units`] to convert from other
+    /// units.
     fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error>;
 
-    /// Get the frequency of the clock
-    /// The unit of the output is milliseconds (of drift) per second,
-    /// compared to the "natural" frequency of the clock.
+    /// Get the frequency of the clock.
+    ///
+    /// The unit of the output is parts per million, i.e. microseconds (of drift) per second,
+    /// compared to the "natural" frequency of the clock. See [`units`] to convert to other
+    /// units.
     fn get_frequency(&self) -> Result<f64, Self::Error>;
 
     /// Change the current time of the clock by an offset.
@@ -71,7 +683,8 @@ pub trait Clock {
     fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error>;
 
     /// Change the indicators for upcoming leap seconds.
-    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<(), Self::Error>;
+    /// Returns the time at which the change was applied.
+    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<Timestamp, Self::Error>;
 
     /// Disable all standard NTP kernel clock discipline. It is all your responsibility now.
     ///
@@ -91,10 +704,764 @@ pub trait Clock {
 
     /// Provide the system with the current best estimates for the statistical
     /// error of the clock, and the maximum deviation due to frequency error and
-    /// distance to the root clock.
+    /// distance to the root clock. See [`dispersion`] for the growth of the maximum error
+    /// between updates.
+    /// Returns the time at which the change was applied.
     fn error_estimate_update(
         &self,
         estimated_error: Duration,
         maximum_error: Duration,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<Timestamp, Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_carries_nanos_into_seconds() {
+        let timestamp = Timestamp {
+            seconds: 0,
+            nanos: 1_000_000_000,
+        };
+
+        assert_ne!(
+            timestamp,
+            Timestamp {
+                seconds: 1,
+                nanos: 0
+            }
+        );
+        assert_eq!(
+            timestamp.normalized(),
+            Timestamp {
+                seconds: 1,
+                nanos: 0
+            }
+        );
+
+        let timestamp = Timestamp {
+            seconds: -2,
+            nanos: 2_500_000_000,
+        };
+
+        assert_eq!(
+            timestamp.normalized(),
+            Timestamp {
+                seconds: 0,
+                nanos: 500_000_000,
+            }
+        );
+
+        let normal = Timestamp {
+            seconds: 5,
+            nanos: 999_999_999,
+        };
+
+        assert_eq!(normal.normalized(), normal);
+    }
+
+    #[test]
+    fn round_to_resolution() {
+        let timestamp = Timestamp {
+            seconds: 1,
+            nanos: 234_500_000,
+        };
+        let millis = Duration::from_millis(1);
+
+        let rounded = |nanos| Timestamp { seconds: 1, nanos };
+        assert_eq!(
+            timestamp.round_to(millis, RoundMode::Down),
+            rounded(234_000_000)
+        );
+        assert_eq!(
+            timestamp.round_to(millis, RoundMode::Up),
+            rounded(235_000_000)
+        );
+        assert_eq!(
+            timestamp.round_to(millis, RoundMode::Nearest),
+            rounded(235_000_000)
+        );
+        assert_eq!(
+            rounded(234_499_999).round_to(millis, RoundMode::Nearest),
+            rounded(234_000_000)
+        );
+
+        // multiples are left alone
+        for mode in [RoundMode::Nearest, RoundMode::Down, RoundMode::Up] {
+            assert_eq!(
+                rounded(234_000_000).round_to(millis, mode),
+                rounded(234_000_000)
+            );
+            assert_eq!(timestamp.round_to(Duration::ZERO, mode), timestamp);
+        }
+    }
+
+    #[test]
+    fn round_to_carries_into_seconds() {
+        let timestamp = Timestamp {
+            seconds: 1,
+            nanos: 999_600_000,
+        };
+        let two = Timestamp {
+            seconds: 2,
+            nanos: 0,
+        };
+
+        assert_eq!(
+            timestamp.round_to(Duration::from_millis(1), RoundMode::Up),
+            two
+        );
+        assert_eq!(
+            timestamp.round_to(Duration::from_millis(1), RoundMode::Nearest),
+            two
+        );
+
+        // before the epoch, rounding down moves away from zero
+        let timestamp = Timestamp {
+            seconds: -1,
+            nanos: 500_000_001,
+        };
+
+        assert_eq!(
+            timestamp.round_to(Duration::from_secs(1), RoundMode::Down),
+            Timestamp {
+                seconds: -1,
+                nanos: 0,
+            }
+        );
+        assert_eq!(
+            timestamp.round_to(Duration::from_secs(1), RoundMode::Up),
+            Timestamp::default()
+        );
+    }
+
+    #[test]
+    fn round_to_out_of_range() {
+        let max = Timestamp {
+            seconds: time_t::MAX,
+            nanos: 1,
+        };
+        assert_eq!(
+            max.checked_round_to(Duration::from_secs(1), RoundMode::Up),
+            None
+        );
+        assert_eq!(
+            max.checked_round_to(Duration::from_secs(1), RoundMode::Down),
+            Some(Timestamp {
+                seconds: time_t::MAX,
+                nanos: 0,
+            })
+        );
+
+        // time_t::MIN is not a multiple of 7 seconds, so the multiple below it is out of range
+        let min = Timestamp {
+            seconds: time_t::MIN,
+            nanos: 0,
+        };
+        let seven = Duration::from_secs(7);
+        assert_eq!(min.checked_round_to(seven, RoundMode::Down), None);
+        assert!(min.checked_round_to(seven, RoundMode::Up).is_some());
+        assert_eq!(
+            min.checked_round_to(Duration::from_secs(1), RoundMode::Down),
+            Some(min)
+        );
+    }
+
+    #[test]
+    fn time_offset_from_duration_signed() {
+        assert_eq!(
+            TimeOffset::from_duration_signed(0, -500_000_000),
+            TimeOffset {
+                seconds: -1,
+                nanos: 500_000_000,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_duration_signed(-2, -250_000_000),
+            TimeOffset {
+                seconds: -3,
+                nanos: 750_000_000,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_duration_signed(1, 1_500_000_000),
+            TimeOffset {
+                seconds: 2,
+                nanos: 500_000_000,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_duration_signed(-3, 0),
+            TimeOffset {
+                seconds: -3,
+                nanos: 0,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "offset out of range")]
+    fn time_offset_from_duration_signed_overflow() {
+        TimeOffset::from_duration_signed(i64::MIN, -1);
+    }
+
+    #[test]
+    fn time_offset_negate() {
+        let half_second = TimeOffset::try_from(Duration::from_millis(500)).unwrap();
+
+        assert_eq!(
+            half_second.negate(),
+            TimeOffset::from_duration_signed(0, -500_000_000)
+        );
+        assert_eq!(half_second.negate().negate(), half_second);
+
+        let whole = TimeOffset {
+            seconds: 2,
+            nanos: 0,
+        };
+
+        assert_eq!(
+            whole.negate(),
+            TimeOffset {
+                seconds: -2,
+                nanos: 0,
+            }
+        );
+        assert_eq!(-whole, whole.negate());
+
+        // only a whole number of time_t::MIN seconds cannot be negated
+        let min = TimeOffset {
+            seconds: time_t::MIN,
+            nanos: 0,
+        };
+        assert_eq!(min.checked_neg(), None);
+
+        let just_above_min = TimeOffset {
+            seconds: time_t::MIN,
+            nanos: 1,
+        };
+        assert_eq!(
+            just_above_min.checked_neg(),
+            Some(TimeOffset {
+                seconds: time_t::MAX,
+                nanos: 999_999_999,
+            })
+        );
+        assert_eq!(just_above_min.negate().negate(), just_above_min);
+    }
+
+    #[test]
+    fn time_offset_from_nanos() {
+        assert_eq!(TimeOffset::from_nanos(0), TimeOffset::zero());
+        assert_eq!(
+            TimeOffset::from_nanos(-500_000_000),
+            TimeOffset {
+                seconds: -1,
+                nanos: 500_000_000,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_nanos(-1_000_000_000),
+            TimeOffset {
+                seconds: -1,
+                nanos: 0,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_nanos(-1_000_000_001),
+            TimeOffset {
+                seconds: -2,
+                nanos: 999_999_999,
+            }
+        );
+        assert_eq!(
+            TimeOffset::from_nanos(2_000_000_003),
+            TimeOffset {
+                seconds: 2,
+                nanos: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn time_offset_neg() {
+        let half_second = TimeOffset::try_from(Duration::from_millis(500)).unwrap();
+
+        assert_eq!(-half_second, TimeOffset::from_nanos(-500_000_000));
+        assert_eq!(-(-half_second), half_second);
+        assert_eq!(-TimeOffset::zero(), TimeOffset::zero());
+        assert_eq!(
+            -TimeOffset::from_nanos(1_000_000_001),
+            TimeOffset::from_nanos(-1_000_000_001)
+        );
+    }
+
+    #[test]
+    fn time_offset_try_from_duration() {
+        assert_eq!(
+            TimeOffset::try_from(Duration::new(3, 250)),
+            Ok(TimeOffset {
+                seconds: 3,
+                nanos: 250,
+            })
+        );
+        assert!(TimeOffset::try_from(Duration::MAX).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn resolution_duration() {
+        let clock = test_clock::MockClock::new(Timestamp::default());
+
+        assert_eq!(
+            clock.resolution().unwrap(),
+            Timestamp {
+                seconds: 0,
+                nanos: 1
+            }
+        );
+        assert_eq!(
+            clock.resolution_duration().unwrap(),
+            Duration::from_nanos(1)
+        );
+    }
+
+    #[test]
+    fn nanos_since_epoch_round_trip() {
+        let cases = [
+            (Timestamp::default(), 0),
+            (
+                Timestamp {
+                    seconds: 1_700_000_000,
+                    nanos: 123_456_789,
+                },
+                1_700_000_000_123_456_789,
+            ),
+            (
+                Timestamp {
+                    seconds: -1,
+                    nanos: 500_000_000,
+                },
+                -500_000_000,
+            ),
+            (
+                Timestamp {
+                    seconds: -3,
+                    nanos: 0,
+                },
+                -3_000_000_000,
+            ),
+        ];
+
+        for (timestamp, nanos) in cases {
+            assert_eq!(timestamp.as_nanos_since_epoch(), nanos);
+            assert_eq!(Timestamp::from_nanos_since_epoch(nanos), timestamp);
+        }
+
+        let max = Timestamp {
+            seconds: i64::MAX as _,
+            nanos: 999_999_999,
+        };
+        assert_eq!(
+            Timestamp::from_nanos_since_epoch(max.as_nanos_since_epoch()),
+            max
+        );
+    }
+
+    #[test]
+    fn try_from_seconds_nanos() {
+        assert_eq!(
+            Timestamp::try_from_seconds_nanos(1_700_000_000, 5),
+            Ok(Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 5,
+            })
+        );
+        assert_eq!(
+            Timestamp::try_from_seconds_nanos(0, 1_000_000_000),
+            Err(TimestampRangeError)
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn try_from_seconds_nanos_after_2038() {
+        let seconds = i64::from(i32::MAX) + 1;
+        assert_eq!(
+            Timestamp::try_from_seconds_nanos(seconds, 0),
+            Ok(Timestamp {
+                seconds: seconds as _,
+                nanos: 0,
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn try_from_seconds_nanos_after_2038() {
+        // glibc and older musl still use a 32-bit time_t here
+        let seconds = i64::from(i32::MAX) + 1;
+        let result = Timestamp::try_from_seconds_nanos(seconds, 0);
+
+        if core::mem::size_of::<libc::time_t>() == 4 {
+            assert_eq!(result, Err(TimestampRangeError));
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+
+    #[test]
+    fn add_duration_carries_into_seconds() {
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 800_000_000,
+        };
+
+        assert_eq!(
+            timestamp + Duration::from_millis(1_300),
+            Timestamp {
+                seconds: 12,
+                nanos: 100_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn sub_duration_borrows_from_seconds() {
+        let timestamp = Timestamp {
+            seconds: 10,
+            nanos: 200_000_000,
+        };
+
+        assert_eq!(
+            timestamp - Duration::from_millis(1_300),
+            Timestamp {
+                seconds: 8,
+                nanos: 900_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn checked_add_overflow() {
+        let timestamp = Timestamp {
+            seconds: i64::MAX as _,
+            nanos: 999_999_999,
+        };
+
+        assert_eq!(timestamp.checked_add(Duration::ZERO), Some(timestamp));
+        assert_eq!(timestamp.checked_add(Duration::from_nanos(1)), None);
+        assert_eq!(timestamp.checked_add(Duration::from_secs(1)), None);
+        assert_eq!(Timestamp::default().checked_add(Duration::MAX), None);
+    }
+
+    #[test]
+    fn checked_sub_overflow() {
+        let timestamp = Timestamp {
+            seconds: libc::time_t::MIN,
+            nanos: 0,
+        };
+
+        assert_eq!(timestamp.checked_sub(Duration::ZERO), Some(timestamp));
+        assert_eq!(timestamp.checked_sub(Duration::from_nanos(1)), None);
+        assert_eq!(Timestamp::default().checked_sub(Duration::MAX), None);
+    }
+
+    #[test]
+    fn duration_since_borrows_from_seconds() {
+        let earlier = Timestamp {
+            seconds: 10,
+            nanos: 900_000_000,
+        };
+        let later = Timestamp {
+            seconds: 12,
+            nanos: 100_000_000,
+        };
+
+        assert_eq!(
+            later.duration_since(&earlier),
+            Some(Duration::from_millis(1_200))
+        );
+        assert_eq!(earlier.duration_since(&later), None);
+        assert_eq!(later.duration_since(&later), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn duration_since_full_range() {
+        let earlier = Timestamp {
+            seconds: libc::time_t::MIN,
+            nanos: 0,
+        };
+        let later = Timestamp {
+            seconds: i64::MAX as _,
+            nanos: 0,
+        };
+
+        assert_eq!(
+            later.duration_since(&earlier),
+            Some(Duration::from_secs(u64::MAX >> (64 - libc::time_t::BITS)))
+        );
+    }
+
+    #[test]
+    fn offset_to_sign() {
+        let a = Timestamp {
+            seconds: 5,
+            nanos: 750_000_000,
+        };
+        let b = Timestamp {
+            seconds: 6,
+            nanos: 250_000_000,
+        };
+
+        assert_eq!(
+            a.offset_to(&b),
+            SignedDuration {
+                negative: false,
+                duration: Duration::from_millis(500),
+            }
+        );
+        assert_eq!(
+            b.offset_to(&a),
+            SignedDuration {
+                negative: true,
+                duration: Duration::from_millis(500),
+            }
+        );
+        assert_eq!(b.offset_to(&a).as_secs_f64(), -0.5);
+        assert_eq!(a.offset_to(&a), SignedDuration::default());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_round_trip() {
+        // 2023-11-14T22:13:20.123456789Z
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let timestamp = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_789,
+        };
+
+        assert_eq!(Timestamp::try_from(time), Ok(timestamp));
+        assert_eq!(SystemTime::try_from(timestamp), Ok(time));
+
+        assert_eq!(Timestamp::try_from(UNIX_EPOCH), Ok(Timestamp::default()));
+        assert_eq!(SystemTime::try_from(Timestamp::default()), Ok(UNIX_EPOCH));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_before_epoch() {
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(
+            Timestamp::try_from(time),
+            Err(FromSystemTimeError::BeforeEpoch)
+        );
+
+        let timestamp = Timestamp {
+            seconds: -1,
+            nanos: 500_000_000,
+        };
+        assert_eq!(
+            SystemTime::try_from(timestamp),
+            Ok(UNIX_EPOCH - Duration::from_millis(500))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time_out_of_range() {
+        // the range of SystemTime depends on the platform, but the conversion never panics
+        let latest = Timestamp {
+            seconds: time_t::MAX,
+            nanos: 999_999_999,
+        };
+        match SystemTime::try_from(latest) {
+            Ok(time) => assert_eq!(Timestamp::try_from(time), Ok(latest)),
+            Err(error) => assert_eq!(error, TimestampRangeError),
+        }
+
+        let earliest = Timestamp {
+            seconds: time_t::MIN,
+            nanos: 0,
+        };
+        if let Ok(time) = SystemTime::try_from(earliest) {
+            assert_eq!(
+                UNIX_EPOCH.duration_since(time).unwrap(),
+                Timestamp::default().offset_to(&earliest).duration
+            );
+        }
+
+        // windows counts 100 ns intervals since 1601 in 64 bits
+        #[cfg(windows)]
+        assert!(SystemTime::try_from(latest).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn offset_date_time_round_trip() {
+        use time::{Date, Month, OffsetDateTime, Time, UtcOffset};
+
+        // the moment of the last leap second so far
+        let datetime = Date::from_calendar_date(2017, Month::January, 1)
+            .unwrap()
+            .with_time(Time::from_hms_nano(0, 0, 0, 250).unwrap())
+            .assume_utc();
+        let timestamp = Timestamp {
+            seconds: 1_483_228_800,
+            nanos: 250,
+        };
+
+        assert_eq!(Timestamp::try_from(datetime), Ok(timestamp));
+        assert_eq!(OffsetDateTime::try_from(timestamp), Ok(datetime));
+
+        // the offset does not change the moment in time
+        let shifted = datetime.to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(Timestamp::try_from(shifted), Ok(timestamp));
+
+        // before the epoch
+        let timestamp = Timestamp {
+            seconds: -1,
+            nanos: 999_999_999,
+        };
+        let datetime = OffsetDateTime::try_from(timestamp).unwrap();
+        assert_eq!(datetime.unix_timestamp_nanos(), -1);
+        assert_eq!(Timestamp::try_from(datetime), Ok(timestamp));
+    }
+
+    #[cfg(all(feature = "time", target_pointer_width = "64"))]
+    #[test]
+    fn offset_date_time_out_of_range() {
+        let timestamp = Timestamp {
+            seconds: i64::MAX as _,
+            nanos: 0,
+        };
+
+        assert!(time::OffsetDateTime::try_from(timestamp).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_round_trip() {
+        use chrono::{DateTime, NaiveDate, Utc};
+
+        let epoch = DateTime::<Utc>::UNIX_EPOCH;
+        assert_eq!(Timestamp::try_from(epoch), Ok(Timestamp::default()));
+        assert_eq!(DateTime::try_from(Timestamp::default()), Ok(epoch));
+
+        let recent = NaiveDate::from_ymd_opt(2024, 2, 29)
+            .unwrap()
+            .and_hms_nano_opt(12, 34, 56, 789)
+            .unwrap()
+            .and_utc();
+        let timestamp = Timestamp {
+            seconds: 1_709_210_096,
+            nanos: 789,
+        };
+        assert_eq!(Timestamp::try_from(recent), Ok(timestamp));
+        assert_eq!(DateTime::try_from(timestamp), Ok(recent));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_leap_second() {
+        use chrono::NaiveDate;
+
+        // 2016-12-31T23:59:60.5Z
+        let leap = NaiveDate::from_ymd_opt(2016, 12, 31)
+            .unwrap()
+            .and_hms_nano_opt(23, 59, 59, 1_500_000_000)
+            .unwrap()
+            .and_utc();
+
+        assert_eq!(
+            Timestamp::try_from(leap),
+            Ok(Timestamp {
+                seconds: 1_483_228_799,
+                nanos: 500_000_000,
+            })
+        );
+    }
+
+    #[cfg(all(feature = "chrono", target_pointer_width = "64"))]
+    #[test]
+    fn chrono_out_of_range() {
+        let timestamp = Timestamp {
+            seconds: i64::MAX as _,
+            nanos: 0,
+        };
+
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::try_from(timestamp),
+            Err(TimestampRangeError)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn display_epoch() {
+        assert_eq!(
+            Timestamp::default().to_string(),
+            "1970-01-01T00:00:00.000000000Z"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn display_leap_day() {
+        let timestamp = Timestamp {
+            seconds: 1_709_210_096,
+            nanos: 123_456_789,
+        };
+
+        assert_eq!(timestamp.to_string(), "2024-02-29T12:34:56.123456789Z");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn display_before_epoch() {
+        let timestamp = Timestamp {
+            seconds: -1,
+            nanos: 500_000_000,
+        };
+        assert_eq!(timestamp.to_string(), "1969-12-31T23:59:59.500000000Z");
+
+        let timestamp = Timestamp {
+            seconds: -2_203_891_200,
+            nanos: 0,
+        };
+        assert_eq!(timestamp.to_string(), "1900-03-01T00:00:00.000000000Z");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let timestamp = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_789,
+        };
+        let json = serde_json::to_string(&timestamp).unwrap();
+        assert_eq!(json, r#"{"seconds":1700000000,"nanos":123456789}"#);
+        assert_eq!(serde_json::from_str::<Timestamp>(&json).unwrap(), timestamp);
+
+        let offset = TimeOffset {
+            seconds: -1,
+            nanos: 500_000_000,
+        };
+        let json = serde_json::to_string(&offset).unwrap();
+        assert_eq!(serde_json::from_str::<TimeOffset>(&json).unwrap(), offset);
+
+        // nanos must be below a second, like everywhere else
+        let json = r#"{"seconds":1,"nanos":1000000000}"#;
+        assert!(serde_json::from_str::<Timestamp>(json).is_err());
+        assert!(serde_json::from_str::<TimeOffset>(json).is_err());
+
+        let json = serde_json::to_string(&LeapIndicator::Leap61).unwrap();
+        assert_eq!(json, r#""Leap61""#);
+        assert_eq!(
+            serde_json::from_str::<LeapIndicator>(&json).unwrap(),
+            LeapIndicator::Leap61
+        );
+    }
 }
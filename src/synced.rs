@@ -0,0 +1,197 @@
+//! Serialize the operations on a clock that is shared between threads.
+//!
+//! Several operations are a read-modify-write of kernel state: e.g.
+//! [`Clock::set_leap_seconds`] on a `UnixClock` reads the status flags, changes the leap bits,
+//! and writes all flags back. When two threads do this at the same time, the first write can be
+//! overwritten with stale flags by the second. [`SyncedClock`] puts a lock around every
+//! operation, so that this cannot happen between users of the same `SyncedClock`. Other
+//! processes steering the same clock can still interfere.
+use crate::{Clock, LeapIndicator, TimeOffset, Timestamp};
+use core::time::Duration;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A [`Clock`] that performs one operation at a time.
+///
+/// ```
+/// use clock_steering::{synced::SyncedClock, unix::UnixClock, Clock};
+/// use std::sync::Arc;
+///
+/// let clock = Arc::new(SyncedClock::new(UnixClock::CLOCK_REALTIME));
+///
+/// let reader = {
+///     let clock = clock.clone();
+///     std::thread::spawn(move || clock.now())
+/// };
+///
+/// assert!(reader.join().unwrap().is_ok());
+/// ```
+#[derive(Debug, Default)]
+pub struct SyncedClock<C> {
+    clock: Mutex<C>,
+}
+
+impl<C> SyncedClock<C> {
+    /// Wrap `clock`, which should not be used elsewhere without the lock
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock: Mutex::new(clock),
+        }
+    }
+
+    /// Hold the lock for several operations, e.g. to call methods that [`Clock`] does not cover.
+    pub fn lock(&self) -> MutexGuard<'_, C> {
+        // the clock has no invariants that a panic halfway through an operation could break
+        self.clock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take the clock out again, e.g. once all threads are done with it
+    pub fn into_inner(self) -> C {
+        self.clock
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C: Clock> Clock for SyncedClock<C> {
+    type Error = C::Error;
+
+    fn now(&self) -> Result<Timestamp, Self::Error> {
+        self.lock().now()
+    }
+
+    fn resolution(&self) -> Result<Timestamp, Self::Error> {
+        self.lock().resolution()
+    }
+
+    fn resolution_duration(&self) -> Result<Duration, Self::Error> {
+        self.lock().resolution_duration()
+    }
+
+    fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
+        self.lock().set_frequency(frequency)
+    }
+
+    fn get_frequency(&self) -> Result<f64, Self::Error> {
+        self.lock().get_frequency()
+    }
+
+    fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+        self.lock().step_clock(offset)
+    }
+
+    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+        self.lock().set_leap_seconds(leap_status)
+    }
+
+    fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+        self.lock().disable_kernel_ntp_algorithm()
+    }
+
+    fn set_tai(&self, tai_offset: i32) -> Result<(), Self::Error> {
+        self.lock().set_tai(tai_offset)
+    }
+
+    fn get_tai(&self) -> Result<i32, Self::Error> {
+        self.lock().get_tai()
+    }
+
+    fn error_estimate_update(
+        &self,
+        est_error: Duration,
+        max_error: Duration,
+    ) -> Result<Timestamp, Self::Error> {
+        self.lock().error_estimate_update(est_error, max_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    };
+
+    /// Sets flags with a separate read and write, like the kernel status flags. `set_tai` sets
+    /// the flag with the given index, `get_tai` reads all flags.
+    #[derive(Default)]
+    struct FlagClock {
+        flags: AtomicI32,
+    }
+
+    impl Clock for FlagClock {
+        type Error = std::io::Error;
+
+        fn now(&self) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+
+        fn resolution(&self) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+
+        fn set_frequency(&self, _frequency: f64) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+
+        fn get_frequency(&self) -> Result<f64, Self::Error> {
+            Ok(0.0)
+        }
+
+        fn step_clock(&self, _offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+
+        fn set_leap_seconds(&self, _leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+
+        fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn set_tai(&self, flag: i32) -> Result<(), Self::Error> {
+            let flags = self.flags.load(Ordering::SeqCst);
+            // give other threads every opportunity to interleave
+            std::thread::yield_now();
+            self.flags.store(flags ^ (1 << flag), Ordering::SeqCst);
+
+            Ok(())
+        }
+
+        fn get_tai(&self) -> Result<i32, Self::Error> {
+            Ok(self.flags.load(Ordering::SeqCst))
+        }
+
+        fn error_estimate_update(
+            &self,
+            _est_error: Duration,
+            _max_error: Duration,
+        ) -> Result<Timestamp, Self::Error> {
+            Ok(Timestamp::default())
+        }
+    }
+
+    #[test]
+    fn concurrent_flag_updates_are_not_lost() {
+        let clock = Arc::new(SyncedClock::new(FlagClock::default()));
+
+        let threads: Vec<_> = (0..8)
+            .map(|flag| {
+                let clock = clock.clone();
+                std::thread::spawn(move || {
+                    // an odd number of toggles leaves the flag set
+                    for _ in 0..1001 {
+                        clock.set_tai(flag).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(clock.get_tai().unwrap(), 0xff);
+    }
+}
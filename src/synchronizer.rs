@@ -0,0 +1,463 @@
+//! Keep one clock synchronized to another, e.g. a PHC to the system clock or vice versa.
+use crate::{discipline::PiController, Clock, SignedDuration, TimeOffset, Timestamp};
+use core::time::Duration;
+
+/// Steers a target clock toward a reference clock with a [`PiController`].
+///
+/// Each [`Synchronizer::tick`] measures the offset between the clocks by reading the target
+/// clock before and after the reference, so the measurement is only as precise as these reads.
+/// [`Synchronizer::with_measure`] replaces this measurement, e.g. with one in the kernel. Offsets of at least the step threshold are corrected by stepping the target clock, smaller
+/// ones by changing its frequency.
+///
+#[cfg_attr(all(target_os = "linux", feature = "std"), doc = "```no_run")]
+#[cfg_attr(not(all(target_os = "linux", feature = "std")), doc = "```ignore")]
+/// use clock_steering::{discipline::PiController, synchronizer::Synchronizer, unix::UnixClock};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     // keep the clock of a network card synchronized to the system clock
+///     let reference = UnixClock::CLOCK_REALTIME;
+///     let target = UnixClock::open("/dev/ptp0")?;
+///     let controller = PiController::new(0.3e6, 0.03e6);
+///
+///     let mut synchronizer = Synchronizer::new(reference, target, controller)
+///         .with_measure(UnixClock::measure_system_offset);
+///
+///     loop {
+///         let report = synchronizer.tick()?;
+///         println!("{report:?}");
+///
+///         std::thread::sleep(Duration::from_secs(1));
+///     }
+/// }
+/// ```
+pub struct Synchronizer<R: Clock, T: Clock> {
+    reference: R,
+    target: T,
+    controller: PiController,
+    step_threshold: Duration,
+    measure: Measure<R, T>,
+    /// The time of the target clock at the previous measurement
+    last_measurement: Option<Timestamp>,
+}
+
+// not derived, because before Rust 1.70 fn pointers taking references don't implement Debug
+impl<R: Clock + core::fmt::Debug, T: Clock + core::fmt::Debug> core::fmt::Debug
+    for Synchronizer<R, T>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Synchronizer")
+            .field("reference", &self.reference)
+            .field("target", &self.target)
+            .field("controller", &self.controller)
+            .field("step_threshold", &self.step_threshold)
+            .field("last_measurement", &self.last_measurement)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Measures the time of the target clock (second argument), and the offset of the reference
+/// clock (first argument) to it, see [`Synchronizer::with_measure`].
+pub type Measure<R, T> =
+    fn(
+        &R,
+        &T,
+    )
+        -> Result<(Timestamp, SignedDuration), SyncError<<R as Clock>::Error, <T as Clock>::Error>>;
+
+/// The outcome of a [`Synchronizer::tick`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncReport {
+    /// The time of the reference minus the time of the target, before correction
+    pub offset: SignedDuration,
+    /// The frequency (in ppm) applied to the target, `None` when it was stepped instead
+    pub frequency: Option<f64>,
+}
+
+impl SyncReport {
+    /// Whether the target clock was stepped
+    pub fn stepped(&self) -> bool {
+        self.frequency.is_none()
+    }
+}
+
+/// An error of either of the clocks of a [`Synchronizer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncError<R, T> {
+    /// Reading the reference clock failed
+    Reference(R),
+    /// Reading or steering the target clock failed
+    Target(T),
+}
+
+impl<R: core::fmt::Display, T: core::fmt::Display> core::fmt::Display for SyncError<R, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Reference(error) => write!(f, "reference clock: {error}"),
+            Self::Target(error) => write!(f, "target clock: {error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::error::Error, T: std::error::Error> std::error::Error for SyncError<R, T> {}
+
+impl<R: Clock, T: Clock> Synchronizer<R, T> {
+    /// The step threshold of ntpd, used until [`Synchronizer::with_step_threshold`] changes it
+    pub const DEFAULT_STEP_THRESHOLD: Duration = Duration::from_millis(125);
+
+    /// Steer `target` toward `reference`, with the frequency corrections of `controller`
+    pub fn new(reference: R, target: T, controller: PiController) -> Self {
+        Self {
+            reference,
+            target,
+            controller,
+            step_threshold: Self::DEFAULT_STEP_THRESHOLD,
+            measure: measure_offset,
+            last_measurement: None,
+        }
+    }
+
+    /// Measure the offset between the clocks with `measure` instead of [`measure_offset`], e.g.
+    /// with [`UnixClock::measure_system_offset`] for a clock device and the system clock.
+    ///
+    /// [`UnixClock::measure_system_offset`]: crate::unix::UnixClock::measure_system_offset
+    pub fn with_measure(mut self, measure: Measure<R, T>) -> Self {
+        self.measure = measure;
+        self
+    }
+
+    /// Step the target clock for offsets of at least `step_threshold`
+    pub fn with_step_threshold(mut self, step_threshold: Duration) -> Self {
+        self.step_threshold = step_threshold;
+        self
+    }
+
+    /// The clock that is followed
+    pub fn reference(&self) -> &R {
+        &self.reference
+    }
+
+    /// The clock that is steered
+    pub fn target(&self) -> &T {
+        &self.target
+    }
+
+    /// The controller, e.g. to read its [`PiController::integral`]
+    pub fn controller(&self) -> &PiController {
+        &self.controller
+    }
+
+    /// Measure the offset between the clocks once, and correct the target clock.
+    pub fn tick(&mut self) -> Result<SyncReport, SyncError<R::Error, T::Error>> {
+        let (target_time, offset) = (self.measure)(&self.reference, &self.target)?;
+
+        if offset.duration >= self.step_threshold {
+            let applied = self
+                .target
+                .step_clock(step_offset(offset))
+                .map_err(SyncError::Target)?;
+
+            // the frequency learned so far remains valid
+            self.last_measurement = Some(applied);
+
+            return Ok(SyncReport {
+                offset,
+                frequency: None,
+            });
+        }
+
+        let interval = self
+            .last_measurement
+            .and_then(|last| target_time.duration_since(&last))
+            .unwrap_or_default();
+        let frequency = self.controller.update(offset, interval);
+
+        self.target
+            .set_frequency(frequency)
+            .map_err(SyncError::Target)?;
+        self.last_measurement = Some(target_time);
+
+        Ok(SyncReport {
+            offset,
+            frequency: Some(frequency),
+        })
+    }
+}
+
+/// Return the time of `target`, and the offset of `reference` to it. This is the default
+/// [`Measure`] of a [`Synchronizer`].
+///
+/// The target clock is read before and after the reference, and the reference is compared to the
+/// midpoint of these reads.
+pub fn measure_offset<R: Clock, T: Clock>(
+    reference: &R,
+    target: &T,
+) -> Result<(Timestamp, SignedDuration), SyncError<R::Error, T::Error>> {
+    let before = target.now().map_err(SyncError::Target)?;
+    let reference = reference.now().map_err(SyncError::Reference)?;
+    let after = target.now().map_err(SyncError::Target)?;
+
+    // the reference was read halfway between the reads of the target
+    let elapsed = after.duration_since(&before).unwrap_or_default();
+    let target = before + elapsed / 2;
+
+    Ok((target, target.offset_to(&reference)))
+}
+
+/// Step `target` once so that it matches `reference`. Returns the time at which the target
+/// clock was stepped.
+///
+/// The offset is measured by reading the reference before and after the target, and comparing
+/// the target to the midpoint of these reads.
+///
+#[cfg_attr(all(unix, feature = "std"), doc = "```no_run")]
+#[cfg_attr(not(all(unix, feature = "std")), doc = "```ignore")]
+/// use clock_steering::{align_clock, unix::UnixClock};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     // start the clock of a network card at the time of the system clock
+///     let target = UnixClock::open("/dev/ptp0")?;
+///     align_clock(&UnixClock::CLOCK_REALTIME, &target)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn align_clock<R: Clock, T: Clock>(
+    reference: &R,
+    target: &T,
+) -> Result<Timestamp, SyncError<R::Error, T::Error>> {
+    let before = reference.now().map_err(SyncError::Reference)?;
+    let target_time = target.now().map_err(SyncError::Target)?;
+    let after = reference.now().map_err(SyncError::Reference)?;
+
+    // the target was read halfway between the reads of the reference
+    let elapsed = after.duration_since(&before).unwrap_or_default();
+    let reference_time = before + elapsed / 2;
+
+    target
+        .step_clock(step_offset(target_time.offset_to(&reference_time)))
+        .map_err(SyncError::Target)
+}
+
+/// The time of the reference clock and of the target clock, read right after each other
+#[cfg(feature = "std")]
+pub type DriftSample = (Timestamp, Timestamp);
+
+/// Read `reference` and `target` `count` times, `interval` apart, and return the pairs of their
+/// times, e.g. to estimate the drift of the target by linear regression on the differences.
+///
+/// The intervals are measured with the monotonic clock of the standard library, from the start
+/// of the sampling, so they do not grow with the time it takes to read the clocks.
+#[cfg(feature = "std")]
+pub fn sample_drift<R: Clock, T: Clock>(
+    reference: &R,
+    target: &T,
+    count: usize,
+    interval: Duration,
+) -> Result<Vec<DriftSample>, SyncError<R::Error, T::Error>> {
+    let start = std::time::Instant::now();
+    let mut deadline = start;
+
+    sample_drift_with(reference, target, count, || {
+        deadline += interval;
+        std::thread::sleep(deadline.saturating_duration_since(std::time::Instant::now()));
+    })
+}
+
+/// Like [`sample_drift`], but calls `wait` between the samples instead of sleeping
+#[cfg(feature = "std")]
+fn sample_drift_with<R: Clock, T: Clock>(
+    reference: &R,
+    target: &T,
+    count: usize,
+    mut wait: impl FnMut(),
+) -> Result<Vec<DriftSample>, SyncError<R::Error, T::Error>> {
+    let mut samples = Vec::with_capacity(count);
+
+    for i in 0..count {
+        if i > 0 {
+            wait();
+        }
+
+        let reference_time = reference.now().map_err(SyncError::Reference)?;
+        let target_time = target.now().map_err(SyncError::Target)?;
+        samples.push((reference_time, target_time));
+    }
+
+    Ok(samples)
+}
+
+fn step_offset(offset: SignedDuration) -> TimeOffset {
+    let seconds = offset.duration.as_secs() as i64;
+    let nanos = offset.duration.subsec_nanos() as i32;
+
+    match offset.negative {
+        false => TimeOffset::from_duration_signed(seconds, nanos),
+        true => TimeOffset::from_duration_signed(-seconds, -nanos),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_clock::MockClock;
+
+    #[test]
+    fn large_offsets_are_stepped() {
+        let reference = MockClock::new(Timestamp {
+            seconds: 10,
+            nanos: 250_000_000,
+        });
+        let target = MockClock::new(Timestamp {
+            seconds: 12,
+            nanos: 0,
+        });
+
+        let controller = PiController::new(0.3e6, 0.03e6);
+        let mut synchronizer = Synchronizer::new(reference, target, controller);
+
+        let report = synchronizer.tick().unwrap();
+        assert!(report.stepped());
+        assert!(report.offset.negative);
+        assert_eq!(report.offset.duration, Duration::from_millis(1750));
+        assert_eq!(
+            synchronizer.target().now().unwrap(),
+            synchronizer.reference().now().unwrap()
+        );
+
+        // once aligned, the frequency takes over
+        let report = synchronizer.tick().unwrap();
+        assert_eq!(report.frequency, Some(0.0));
+    }
+
+    #[test]
+    fn custom_measure_is_used() {
+        let reference = MockClock::new(Timestamp::default());
+        let target = MockClock::new(Timestamp::default());
+
+        // the clocks agree, but the measurement says the reference is 10 µs ahead
+        let controller = PiController::new(0.3e6, 0.03e6);
+        let mut synchronizer =
+            Synchronizer::new(reference, target, controller).with_measure(|_, target| {
+                let offset = SignedDuration {
+                    duration: Duration::from_micros(10),
+                    negative: false,
+                };
+
+                Ok((target.now().map_err(SyncError::Target)?, offset))
+            });
+
+        let report = synchronizer.tick().unwrap();
+        assert_eq!(report.offset.duration, Duration::from_micros(10));
+        assert!(!report.offset.negative);
+        assert!(report.frequency.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn align_clock_steps_target() {
+        let reference = MockClock::new(Timestamp {
+            seconds: 100,
+            nanos: 0,
+        });
+
+        // a target that is ahead is stepped back
+        let target = MockClock::new(Timestamp {
+            seconds: 105,
+            nanos: 500_000_000,
+        });
+
+        let applied = align_clock(&reference, &target).unwrap();
+        assert_eq!(applied, reference.now().unwrap());
+        assert_eq!(target.now().unwrap(), reference.now().unwrap());
+
+        // a target that is behind is stepped forward
+        let target = MockClock::new(Timestamp {
+            seconds: 99,
+            nanos: 750_000_000,
+        });
+
+        align_clock(&reference, &target).unwrap();
+        assert_eq!(target.now().unwrap(), reference.now().unwrap());
+
+        // already aligned clocks are stepped by zero
+        let applied = align_clock(&reference, &target).unwrap();
+        assert_eq!(applied, reference.now().unwrap());
+    }
+
+    #[test]
+    fn sample_drift_shows_linear_trend() {
+        let reference = MockClock::new(Timestamp::default());
+        let target = MockClock::new(Timestamp {
+            seconds: 5,
+            nanos: 0,
+        });
+
+        // the target runs 20 ppm fast
+        target.set_frequency(20.0).unwrap();
+
+        let samples = sample_drift_with(&reference, &target, 5, || {
+            reference.advance(Duration::from_secs(10));
+            target.advance(Duration::from_secs(10));
+        })
+        .unwrap();
+
+        assert_eq!(samples.len(), 5);
+
+        for (i, (reference_time, target_time)) in samples.into_iter().enumerate() {
+            let offset = reference_time.offset_to(&target_time);
+
+            assert!(!offset.negative);
+            assert_eq!(
+                offset.duration,
+                Duration::from_secs(5) + Duration::from_micros(200) * i as u32
+            );
+        }
+    }
+
+    #[test]
+    fn sample_drift_without_waiting() {
+        let reference = MockClock::new(Timestamp::default());
+        let target = MockClock::new(Timestamp::default());
+
+        let samples = sample_drift(&reference, &target, 3, Duration::ZERO).unwrap();
+        assert_eq!(
+            samples,
+            vec![(Timestamp::default(), Timestamp::default()); 3]
+        );
+
+        assert!(
+            sample_drift(&reference, &target, 0, Duration::from_secs(60))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn drifting_clocks_converge() {
+        // the reference runs 50 ppm fast, and starts 1ms ahead
+        let reference = MockClock::new(Timestamp {
+            seconds: 0,
+            nanos: 1_000_000,
+        });
+        reference.set_frequency(50.0).unwrap();
+        let target = MockClock::new(Timestamp::default());
+
+        let controller = PiController::new(0.3e6, 0.03e6);
+        let mut synchronizer = Synchronizer::new(reference, target, controller);
+
+        let mut report = synchronizer.tick().unwrap();
+        assert!(!report.stepped());
+
+        for _ in 0..500 {
+            synchronizer.reference().advance(Duration::from_secs(1));
+            synchronizer.target().advance(Duration::from_secs(1));
+
+            report = synchronizer.tick().unwrap();
+        }
+
+        assert!(report.offset.duration < Duration::from_micros(1));
+        assert!((report.frequency.unwrap() - 50.0).abs() < 0.1);
+        assert!((synchronizer.controller().integral() - 50.0).abs() < 0.1);
+    }
+}
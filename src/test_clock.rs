@@ -0,0 +1,258 @@
+//! An in-memory [`Clock`] for testing code that steers a clock.
+use crate::{Clock, LeapIndicator, TimeOffset, Timestamp};
+use std::{convert::Infallible, sync::Mutex, time::Duration};
+
+/// A steering operation that was applied to a [`MockClock`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    /// [`Clock::set_frequency`] was called
+    SetFrequency(f64),
+    /// [`Clock::step_clock`] was called
+    StepClock(TimeOffset),
+    /// [`Clock::set_leap_seconds`] was called
+    SetLeapSeconds(LeapIndicator),
+    /// [`Clock::set_tai`] was called
+    SetTai(i32),
+    /// [`Clock::error_estimate_update`] was called
+    ErrorEstimateUpdate {
+        estimated_error: Duration,
+        maximum_error: Duration,
+    },
+    /// [`Clock::disable_kernel_ntp_algorithm`] was called
+    DisableKernelNtpAlgorithm,
+}
+
+#[derive(Debug)]
+struct State {
+    now: Timestamp,
+    frequency: f64,
+    tai: i32,
+    history: Vec<Operation>,
+}
+
+/// A simulated clock that only moves when [`MockClock::advance`] is called.
+///
+/// The frequency set with [`Clock::set_frequency`] skews how far the clock moves, and every
+/// steering operation is recorded so it can be inspected with [`MockClock::history`].
+///
+/// ```
+/// use clock_steering::{test_clock::MockClock, Clock, Timestamp};
+/// use std::time::Duration;
+///
+/// let clock = MockClock::new(Timestamp::default());
+///
+/// clock.set_frequency(100.0).unwrap();
+/// clock.advance(Duration::from_secs(1));
+///
+/// assert_eq!(clock.now().unwrap().nanos, 100_000);
+/// ```
+#[derive(Debug)]
+pub struct MockClock {
+    state: Mutex<State>,
+}
+
+impl MockClock {
+    /// Create a clock that reads `start` until it is advanced
+    pub fn new(start: Timestamp) -> Self {
+        Self {
+            state: Mutex::new(State {
+                now: start,
+                frequency: 0.0,
+                tai: 0,
+                history: Vec::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state is never left inconsistent, so it's fine to ignore poisoning
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Let `elapsed` of real time pass. The clock moves by `elapsed`, skewed by its current
+    /// frequency.
+    pub fn advance(&self, elapsed: Duration) {
+        let mut state = self.state();
+
+        let skewed = elapsed.as_nanos() as f64 * (1.0 + state.frequency * 1e-6);
+        state.now = state.now + Duration::from_nanos(skewed.round() as u64);
+    }
+
+    /// All steering operations applied to this clock so far, in order
+    pub fn history(&self) -> Vec<Operation> {
+        self.state().history.clone()
+    }
+}
+
+impl Clock for MockClock {
+    type Error = Infallible;
+
+    fn now(&self) -> Result<Timestamp, Self::Error> {
+        Ok(self.state().now)
+    }
+
+    fn resolution(&self) -> Result<Timestamp, Self::Error> {
+        Ok(Timestamp {
+            seconds: 0,
+            nanos: 1,
+        })
+    }
+
+    fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
+        let mut state = self.state();
+        state.frequency = frequency;
+        state.history.push(Operation::SetFrequency(frequency));
+
+        Ok(state.now)
+    }
+
+    fn get_frequency(&self) -> Result<f64, Self::Error> {
+        Ok(self.state().frequency)
+    }
+
+    fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+        let mut state = self.state();
+
+        let seconds = state.now.seconds + offset.seconds;
+        state.now = Timestamp { seconds, nanos: 0 }
+            + Duration::from_nanos(state.now.nanos as u64 + offset.nanos as u64);
+        state.history.push(Operation::StepClock(offset));
+
+        Ok(state.now)
+    }
+
+    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+        let mut state = self.state();
+        state.history.push(Operation::SetLeapSeconds(leap_status));
+
+        Ok(state.now)
+    }
+
+    fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+        let mut state = self.state();
+        state.history.push(Operation::DisableKernelNtpAlgorithm);
+
+        Ok(())
+    }
+
+    fn set_tai(&self, tai_offset: i32) -> Result<(), Self::Error> {
+        let mut state = self.state();
+        state.tai = tai_offset;
+        state.history.push(Operation::SetTai(tai_offset));
+
+        Ok(())
+    }
+
+    fn get_tai(&self) -> Result<i32, Self::Error> {
+        Ok(self.state().tai)
+    }
+
+    fn error_estimate_update(
+        &self,
+        estimated_error: Duration,
+        maximum_error: Duration,
+    ) -> Result<Timestamp, Self::Error> {
+        let mut state = self.state();
+        state.history.push(Operation::ErrorEstimateUpdate {
+            estimated_error,
+            maximum_error,
+        });
+
+        Ok(state.now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_without_skew() {
+        let clock = MockClock::new(Timestamp {
+            seconds: 100,
+            nanos: 0,
+        });
+
+        clock.advance(Duration::from_millis(1500));
+
+        assert_eq!(
+            clock.now().unwrap(),
+            Timestamp {
+                seconds: 101,
+                nanos: 500_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn frequency_skews_now() {
+        let clock = MockClock::new(Timestamp::default());
+
+        clock.set_frequency(-250.0).unwrap();
+        clock.advance(Duration::from_secs(10));
+
+        assert_eq!(
+            clock.now().unwrap(),
+            Timestamp {
+                seconds: 9,
+                nanos: 997_500_000,
+            }
+        );
+
+        clock.set_frequency(500.0).unwrap();
+        clock.advance(Duration::from_secs(2));
+
+        assert_eq!(
+            clock.now().unwrap(),
+            Timestamp {
+                seconds: 11,
+                nanos: 998_500_000,
+            }
+        );
+    }
+
+    #[test]
+    fn updates_return_the_current_time() {
+        let now = Timestamp {
+            seconds: 42,
+            nanos: 7,
+        };
+        let clock = MockClock::new(now);
+
+        assert_eq!(clock.set_leap_seconds(LeapIndicator::Leap61), Ok(now));
+        assert_eq!(
+            clock.error_estimate_update(Duration::from_micros(5), Duration::from_millis(1)),
+            Ok(now)
+        );
+    }
+
+    #[test]
+    fn step_clock_records_history() {
+        let clock = MockClock::new(Timestamp {
+            seconds: 10,
+            nanos: 800_000_000,
+        });
+
+        let offset = TimeOffset {
+            seconds: -1,
+            nanos: 500_000_000,
+        };
+        let applied = clock.step_clock(offset).unwrap();
+
+        assert_eq!(
+            applied,
+            Timestamp {
+                seconds: 10,
+                nanos: 300_000_000,
+            }
+        );
+        assert_eq!(clock.now().unwrap(), applied);
+
+        clock.set_frequency(12.5).unwrap();
+
+        assert_eq!(
+            clock.history(),
+            vec![Operation::StepClock(offset), Operation::SetFrequency(12.5)]
+        );
+    }
+}
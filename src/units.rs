@@ -0,0 +1,76 @@
+//! Conversions between the frequency units of [`Clock::set_frequency`](crate::Clock::set_frequency)
+//! (parts per million) and the units used elsewhere, such as in PTP messages.
+//!
+//! A frequency offset is the drift of a clock relative to its nominal rate: 1 ppm means that the
+//! clock gains a microsecond every second.
+
+/// One nanosecond per second in PTP `scaledNanoseconds` (nanoseconds multiplied by 2^16)
+const SCALED_NANOS_PER_NANO: f64 = 65536.0;
+
+/// Convert a frequency offset in scaled nanoseconds per second (nanoseconds per second
+/// multiplied by 2^16, the `scaledNanoseconds` format of PTP) to parts per million.
+pub fn ppm_from_scaled_ns_per_s(scaled: i64) -> f64 {
+    scaled as f64 / SCALED_NANOS_PER_NANO / 1000.0
+}
+
+/// Convert a frequency offset in parts per million to scaled nanoseconds per second, rounding to
+/// the nearest unit. Offsets that do not fit saturate at [`i64::MIN`] and [`i64::MAX`], and NaN
+/// converts to 0.
+pub fn scaled_ns_per_s_from_ppm(ppm: f64) -> i64 {
+    let scaled = ppm * 1000.0 * SCALED_NANOS_PER_NANO;
+
+    // f64::round is not available without std; float to int casts saturate
+    if scaled < 0.0 {
+        (scaled - 0.5) as i64
+    } else {
+        (scaled + 0.5) as i64
+    }
+}
+
+/// Convert a frequency offset in parts per billion to seconds (of drift) per second.
+pub fn seconds_per_second_from_ppb(ppb: f64) -> f64 {
+    ppb * 1e-9
+}
+
+/// Convert a frequency offset in seconds (of drift) per second to parts per billion.
+pub fn ppb_from_seconds_per_second(seconds_per_second: f64) -> f64 {
+    seconds_per_second * 1e9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_ns_per_s_round_trip() {
+        // 1 ns/s is 2^16 scaled nanoseconds per second, and a thousandth of a ppm
+        assert_eq!(ppm_from_scaled_ns_per_s(65536), 0.001);
+        assert_eq!(scaled_ns_per_s_from_ppm(0.001), 65536);
+
+        // the 500 ppm limit of the kernel NTP API
+        assert_eq!(scaled_ns_per_s_from_ppm(-500.0), -32_768_000_000);
+        assert_eq!(ppm_from_scaled_ns_per_s(-32_768_000_000), -500.0);
+
+        // 2^-16 ns/s is the smallest step
+        assert_eq!(scaled_ns_per_s_from_ppm(1e-3 / 65536.0), 1);
+        assert_eq!(scaled_ns_per_s_from_ppm(-1e-3 / 65536.0), -1);
+        assert_eq!(scaled_ns_per_s_from_ppm(0.4e-3 / 65536.0), 0);
+    }
+
+    #[test]
+    fn scaled_ns_per_s_saturates() {
+        assert_eq!(scaled_ns_per_s_from_ppm(f64::INFINITY), i64::MAX);
+        assert_eq!(scaled_ns_per_s_from_ppm(-1e30), i64::MIN);
+        assert_eq!(scaled_ns_per_s_from_ppm(f64::NAN), 0);
+    }
+
+    #[test]
+    fn seconds_per_second() {
+        assert_eq!(seconds_per_second_from_ppb(1e9), 1.0);
+        assert_eq!(ppb_from_seconds_per_second(1.0), 1e9);
+
+        // a 50 ppm fast clock
+        assert!((seconds_per_second_from_ppb(50_000.0) - 50e-6).abs() < 1e-18);
+        assert!((ppb_from_seconds_per_second(50e-6) - 50_000.0).abs() < 1e-6);
+    }
+}
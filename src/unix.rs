@@ -1,17 +1,347 @@
-use crate::{Clock, LeapIndicator, TimeOffset, Timestamp};
-use std::time::Duration;
+use crate::{
+    synchronizer::{DriftSample, SyncError},
+    Clock, LeapIndicator, SignedDuration, TimeOffset, Timestamp,
+};
 #[cfg(target_os = "linux")]
 use std::{
-    os::unix::io::{IntoRawFd, RawFd},
-    path::Path,
+    marker::PhantomData,
+    ops::Deref,
+    os::unix::io::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicI32},
+        Arc,
+    },
+};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
 
 /// A Unix OS clock
-#[derive(Debug, Clone, Copy)]
+///
+/// A clock created with [`UnixClock::open`] owns the file descriptor of the clock device. Clones
+/// share the file descriptor, which is closed when the last clone is dropped.
+///
+/// Changing the status flags (e.g. with [`Clock::set_leap_seconds`]) reads the flags and then
+/// writes them back. Concurrent calls can therefore lose each other's changes; wrap a clock that
+/// is shared between threads in a [`SyncedClock`](crate::synced::SyncedClock).
+#[derive(Debug)]
 pub struct UnixClock {
     clock: libc::clockid_t,
     #[cfg(target_os = "linux")]
-    fd: Option<RawFd>,
+    fd: Option<ClockFd>,
+    #[cfg(target_os = "linux")]
+    cache: Option<Arc<DeviceCache>>,
+    frequency_only: bool,
+}
+
+/// Properties of a clock device that are read on first use, and then cached. Clones of the clock
+/// share the cache.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct DeviceCache {
+    max_adj: AtomicI32,
+    /// Whether `max_adj` has been read, because any value (including 0) is a valid `max_adj`
+    max_adj_read: AtomicBool,
+    resolution_nanos: AtomicU64,
+}
+
+#[cfg(target_os = "linux")]
+impl Default for DeviceCache {
+    fn default() -> Self {
+        Self {
+            max_adj: AtomicI32::new(0),
+            max_adj_read: AtomicBool::new(false),
+            resolution_nanos: AtomicU64::new(UNREAD_RESOLUTION),
+        }
+    }
+}
+
+/// Marks a cached resolution (in nanoseconds) that has not been read yet
+const UNREAD_RESOLUTION: u64 = u64::MAX;
+
+// The resolutions of the kernel clocks, which do not change while the system runs
+static REALTIME_RESOLUTION: AtomicU64 = AtomicU64::new(UNREAD_RESOLUTION);
+static MONOTONIC_RESOLUTION: AtomicU64 = AtomicU64::new(UNREAD_RESOLUTION);
+#[cfg(target_os = "linux")]
+static TAI_RESOLUTION: AtomicU64 = AtomicU64::new(UNREAD_RESOLUTION);
+#[cfg(target_os = "linux")]
+static MONOTONIC_RAW_RESOLUTION: AtomicU64 = AtomicU64::new(UNREAD_RESOLUTION);
+#[cfg(target_os = "linux")]
+static BOOTTIME_RESOLUTION: AtomicU64 = AtomicU64::new(UNREAD_RESOLUTION);
+
+/// The cached resolution of a kernel clock, `None` for clocks that are not cached
+fn kernel_resolution_cache(clock: libc::clockid_t) -> Option<&'static AtomicU64> {
+    match clock {
+        libc::CLOCK_REALTIME => Some(&REALTIME_RESOLUTION),
+        libc::CLOCK_MONOTONIC => Some(&MONOTONIC_RESOLUTION),
+        #[cfg(target_os = "linux")]
+        libc::CLOCK_TAI => Some(&TAI_RESOLUTION),
+        #[cfg(target_os = "linux")]
+        libc::CLOCK_MONOTONIC_RAW => Some(&MONOTONIC_RAW_RESOLUTION),
+        #[cfg(target_os = "linux")]
+        libc::CLOCK_BOOTTIME => Some(&BOOTTIME_RESOLUTION),
+        _ => None,
+    }
+}
+
+/// The resolution in `cache`, or from `read` when it has not been read before
+fn cached_resolution_in(
+    cache: &AtomicU64,
+    read: impl FnOnce() -> Result<Timestamp, Error>,
+) -> Result<Timestamp, Error> {
+    let nanos = cache.load(Ordering::Relaxed);
+    if nanos != UNREAD_RESOLUTION {
+        return Ok(Timestamp::from_nanos_since_epoch(nanos.into()));
+    }
+
+    let resolution = read()?;
+
+    // a resolution that does not fit is not cached
+    if let Ok(nanos) = u64::try_from(resolution.as_nanos_since_epoch()) {
+        cache.store(nanos, Ordering::Relaxed);
+    }
+
+    Ok(resolution)
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+enum ClockFd {
+    Owned(Arc<OwnedFd>),
+    // the lifetime is tracked by `BorrowedUnixClock`
+    Borrowed(RawFd),
+}
+
+impl Clone for UnixClock {
+    /// See [`UnixClock::try_clone`], which does not panic.
+    ///
+    /// # Panics
+    ///
+    /// When a borrowed file descriptor cannot be duplicated, e.g. because the process has run
+    /// out of file descriptors.
+    fn clone(&self) -> Self {
+        self.try_clone()
+            .expect("failed to duplicate the clock file descriptor")
+    }
+}
+
+/// A clock device that borrows its file descriptor, see [`UnixClock::from_borrowed_fd`].
+///
+/// Dereferences to a [`UnixClock`], which does not close the file descriptor when dropped.
+/// Cloning that clock duplicates the file descriptor, see [`UnixClock::try_clone`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct BorrowedUnixClock<'fd> {
+    clock: UnixClock,
+    _fd: PhantomData<BorrowedFd<'fd>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Deref for BorrowedUnixClock<'_> {
+    type Target = UnixClock;
+
+    fn deref(&self) -> &Self::Target {
+        &self.clock
+    }
+}
+
+/// A clock device whose external timestamp events can be awaited.
+///
+/// Only [`AsyncUnixClock::read_external_timestamp`] is truly asynchronous: it waits for the
+/// device to become readable on the tokio reactor. Use
+/// [`BlockingClock`](crate::async_clock::BlockingClock) to steer the clock without blocking the
+/// executor.
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+#[derive(Debug)]
+pub struct AsyncUnixClock {
+    // must be dropped (and deregistered) before the clock closes the fd
+    fd: tokio::io::unix::AsyncFd<RawFd>,
+    clock: UnixClock,
+}
+
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+impl AsyncUnixClock {
+    /// Register a clock device with the tokio reactor. The file descriptor is switched to
+    /// non-blocking mode.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub fn new(clock: UnixClock) -> Result<Self, Error> {
+        let Some(fd) = clock.raw_fd() else {
+            return Err(Error::Invalid);
+        };
+
+        set_fd_nonblocking(fd, true)?;
+
+        let fd = tokio::io::unix::AsyncFd::with_interest(fd, tokio::io::Interest::READABLE)
+            .map_err(error_from_io)?;
+
+        Ok(Self { fd, clock })
+    }
+
+    /// The underlying clock, for steering and for enabling external timestamps
+    pub fn clock(&self) -> &UnixClock {
+        &self.clock
+    }
+
+    /// Wait for the next external timestamp event, see [`UnixClock::read_external_timestamp`].
+    pub async fn read_external_timestamp(&self) -> Result<PtpExtTsEvent, Error> {
+        loop {
+            let mut guard = self.fd.readable().await.map_err(error_from_io)?;
+
+            match try_read_external_timestamp(*self.fd.get_ref())? {
+                Some(event) => return Ok(event),
+                None => guard.clear_ready(),
+            }
+        }
+    }
+}
+
+/// Opens and prepares a [`UnixClock`] in one go.
+///
+/// The options are applied in order: open the device, switch it to non-blocking mode, then
+/// disable the kernel NTP algorithm. The first failure is returned.
+///
+/// ```no_run
+/// use clock_steering::{unix::UnixClockBuilder, TimeOffset};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), clock_steering::unix::Error> {
+///     let clock = UnixClockBuilder::new()
+///         .path("/dev/ptp0")
+///         .disable_kernel_ntp(true)
+///         .step_threshold(Duration::from_millis(10))
+///         .build()?;
+///
+///     clock.correct(TimeOffset { seconds: 0, nanos: 500 })?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixClockBuilder {
+    #[cfg(target_os = "linux")]
+    path: Option<PathBuf>,
+    disable_kernel_ntp: bool,
+    #[cfg(target_os = "linux")]
+    nonblocking: bool,
+    step_threshold: Duration,
+}
+
+impl Default for UnixClockBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnixClockBuilder {
+    /// The step threshold of ntpd, used when none is configured
+    pub const DEFAULT_STEP_THRESHOLD: Duration = Duration::from_millis(125);
+
+    /// A builder for [`UnixClock::CLOCK_REALTIME`], with all options off
+    pub fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            path: None,
+            disable_kernel_ntp: false,
+            #[cfg(target_os = "linux")]
+            nonblocking: false,
+            step_threshold: Self::DEFAULT_STEP_THRESHOLD,
+        }
+    }
+
+    /// Open the clock device at `path` (see [`UnixClock::open`]) instead of using the realtime
+    /// clock
+    #[cfg(target_os = "linux")]
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Disable the kernel clock discipline, see [`Clock::disable_kernel_ntp_algorithm`]
+    pub fn disable_kernel_ntp(mut self, disable: bool) -> Self {
+        self.disable_kernel_ntp = disable;
+        self
+    }
+
+    /// Make reading external timestamps non-blocking, see [`UnixClock::set_nonblocking`]. This
+    /// requires a clock device.
+    #[cfg(target_os = "linux")]
+    pub fn nonblocking(mut self, nonblocking: bool) -> Self {
+        self.nonblocking = nonblocking;
+        self
+    }
+
+    /// The offset from which [`ConfiguredUnixClock::correct`] steps rather than slews. Offsets
+    /// beyond [`UnixClock::MAX_SLEW`] cannot be slewed, see [`UnixClock::correct_offset`].
+    pub fn step_threshold(mut self, step_threshold: Duration) -> Self {
+        self.step_threshold = step_threshold;
+        self
+    }
+
+    /// Open the clock and apply the options.
+    pub fn build(self) -> Result<ConfiguredUnixClock, Error> {
+        #[cfg(target_os = "linux")]
+        let clock = match &self.path {
+            Some(path) => UnixClock::open(path).map_err(error_from_io)?,
+            None => UnixClock::CLOCK_REALTIME,
+        };
+        #[cfg(not(target_os = "linux"))]
+        let clock = UnixClock::CLOCK_REALTIME;
+
+        #[cfg(target_os = "linux")]
+        if self.nonblocking {
+            clock.set_nonblocking(true)?;
+        }
+
+        if self.disable_kernel_ntp {
+            clock.disable_kernel_ntp_algorithm()?;
+        }
+
+        Ok(ConfiguredUnixClock {
+            clock,
+            step_threshold: self.step_threshold,
+        })
+    }
+}
+
+/// A clock prepared by a [`UnixClockBuilder`], which retains its configuration.
+///
+/// Dereferences to the [`UnixClock`].
+#[derive(Debug)]
+pub struct ConfiguredUnixClock {
+    clock: UnixClock,
+    step_threshold: Duration,
+}
+
+impl ConfiguredUnixClock {
+    /// The configured step threshold, see [`UnixClockBuilder::step_threshold`]
+    pub fn step_threshold(&self) -> Duration {
+        self.step_threshold
+    }
+
+    /// Correct the clock with [`UnixClock::correct_offset`], using the configured step threshold
+    pub fn correct(&self, offset: TimeOffset) -> Result<Correction, Error> {
+        self.clock.correct_offset(offset, self.step_threshold)
+    }
+
+    /// Release the clock, dropping the configuration
+    pub fn into_inner(self) -> UnixClock {
+        self.clock
+    }
+}
+
+impl std::ops::Deref for ConfiguredUnixClock {
+    type Target = UnixClock;
+
+    fn deref(&self) -> &Self::Target {
+        &self.clock
+    }
 }
 
 impl UnixClock {
@@ -33,6 +363,9 @@ impl UnixClock {
         clock: libc::CLOCK_REALTIME,
         #[cfg(target_os = "linux")]
         fd: None,
+        #[cfg(target_os = "linux")]
+        cache: None,
+        frequency_only: false,
     };
 
     /// TAI time on linux systems.
@@ -53,10 +386,92 @@ impl UnixClock {
     pub const CLOCK_TAI: Self = UnixClock {
         clock: libc::CLOCK_TAI,
         fd: None,
+        cache: None,
+        frequency_only: false,
+    };
+
+    /// The monotonic clock on unix systems, which cannot be set but is affected by frequency
+    /// adjustments of the realtime clock.
+    ///
+    /// This clock can only be read: stepping or changing the frequency of a monotonic clock is
+    /// meaningless, and these operations return [`Error::NotSupported`].
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let clock = UnixClock::CLOCK_MONOTONIC;
+    ///     let now = clock.now()?;
+    ///
+    ///     println!("{now:?}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const CLOCK_MONOTONIC: Self = UnixClock {
+        clock: libc::CLOCK_MONOTONIC,
+        #[cfg(target_os = "linux")]
+        fd: None,
+        #[cfg(target_os = "linux")]
+        cache: None,
+        frequency_only: false,
+    };
+
+    /// The raw monotonic clock on linux systems, which is not affected by frequency adjustments.
+    ///
+    /// This clock can only be read: stepping or changing the frequency of a monotonic clock is
+    /// meaningless, and these operations return [`Error::NotSupported`].
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let clock = UnixClock::CLOCK_MONOTONIC_RAW;
+    ///     let now = clock.now()?;
+    ///
+    ///     println!("{now:?}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub const CLOCK_MONOTONIC_RAW: Self = UnixClock {
+        clock: libc::CLOCK_MONOTONIC_RAW,
+        fd: None,
+        cache: None,
+        frequency_only: false,
+    };
+
+    /// The boot time clock on linux systems. Like [`UnixClock::CLOCK_MONOTONIC`], but it keeps
+    /// counting while the system is suspended, whereas the monotonic clock pauses.
+    ///
+    /// This clock can only be read: stepping or changing the frequency of a monotonic clock is
+    /// meaningless, and these operations return [`Error::NotSupported`].
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let clock = UnixClock::CLOCK_BOOTTIME;
+    ///     let now = clock.now()?;
+    ///
+    ///     println!("{now:?}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub const CLOCK_BOOTTIME: Self = UnixClock {
+        clock: libc::CLOCK_BOOTTIME,
+        fd: None,
+        cache: None,
+        frequency_only: false,
     };
 
     /// Open a clock device.
     ///
+    /// The device is closed when the returned clock is dropped.
+    ///
     /// ```no_run
     /// use clock_steering::{Clock, unix::UnixClock};
     ///
@@ -76,20 +491,202 @@ impl UnixClock {
             .read(true)
             .open(path)?;
 
-        // we need an owned fd. the file will be closed when the process exits.
-        Ok(Self::safe_from_raw_fd(file.into_raw_fd()))
+        Ok(Self::from_owned_fd(file.into()))
+    }
+
+    /// The largest index of a clock device (PHC): the kernel numbers them below 2^20 (its
+    /// `MINORMASK`)
+    #[cfg(target_os = "linux")]
+    pub const MAX_PHC_INDEX: u32 = (1 << 20) - 1;
+
+    /// Open the clock device (PHC) with the given index, as in `/dev/ptp<index>`, e.g. the index
+    /// that `ethtool -T` reports.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidInput`] for indices above
+    /// [`UnixClock::MAX_PHC_INDEX`], and with [`std::io::ErrorKind::NotFound`] (naming the
+    /// path) when the device does not exist.
+    #[cfg(target_os = "linux")]
+    pub fn open_index(index: u32) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        if index > Self::MAX_PHC_INDEX {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("clock device index {index} is out of range"),
+            ));
+        }
+
+        let path = phc_device_path(index as i32);
+
+        Self::open(&path).map_err(|error| match error.kind() {
+            ErrorKind::NotFound => Error::new(
+                ErrorKind::NotFound,
+                format!("clock device {} does not exist", path.display()),
+            ),
+            _ => error,
+        })
     }
 
-    // Consume an fd and produce a clock id. Clock id is only valid
-    // so long as the fd is open, so the RawFd here should
-    // not be borrowed.
+    /// Open the clock device (PHC) of a network interface, see [`phc_index_for_interface`].
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let clock = UnixClock::open_for_interface("eth0")?;
+    ///     let now = clock.now()?;
+    ///
+    ///     println!("{now:?}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
     #[cfg(target_os = "linux")]
-    fn safe_from_raw_fd(fd: RawFd) -> Self {
-        let clock = ((!(fd as libc::clockid_t)) << 3) | 3;
+    pub fn open_for_interface(name: &str) -> std::io::Result<Self> {
+        let index = phc_index_for_interface(name)?;
+        Self::open(phc_device_path(index))
+    }
 
+    // Take ownership of an fd and produce a clock id. Clock id is only valid
+    // so long as the fd is open, which is why the clock owns the fd.
+    #[cfg(target_os = "linux")]
+    fn from_owned_fd(fd: OwnedFd) -> Self {
         Self {
-            clock,
-            fd: Some(fd),
+            clock: clock_id_from_fd(fd.as_raw_fd()),
+            fd: Some(ClockFd::Owned(Arc::new(fd))),
+            cache: Some(Arc::default()),
+            frequency_only: false,
+        }
+    }
+
+    /// Use a clock device whose file descriptor is managed elsewhere.
+    ///
+    /// Unlike [`UnixClock::open`], the file descriptor is not closed when the clock is dropped.
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    /// use std::os::unix::io::AsFd;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let file = std::fs::File::open("/dev/ptp0")?;
+    ///     let clock = UnixClock::from_borrowed_fd(file.as_fd());
+    ///     let now = clock.now()?;
+    ///
+    ///     println!("{now:?}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn from_borrowed_fd(fd: BorrowedFd<'_>) -> BorrowedUnixClock<'_> {
+        let fd = fd.as_raw_fd();
+
+        BorrowedUnixClock {
+            clock: Self {
+                clock: clock_id_from_fd(fd),
+                fd: Some(ClockFd::Borrowed(fd)),
+                cache: Some(Arc::default()),
+                frequency_only: false,
+            },
+            _fd: PhantomData,
+        }
+    }
+
+    /// Clone this clock. Clones of a clock device share its file descriptor.
+    ///
+    /// A clock that borrows its file descriptor (see [`UnixClock::from_borrowed_fd`]) is cloned
+    /// with a duplicate of it instead, because the clone may outlive the borrow. The clone then
+    /// has a different [`UnixClock::raw_clock_id`], derived from the new file descriptor, for the
+    /// same device. Duplicating the file descriptor fails when the process has run out of them.
+    pub fn try_clone(&self) -> Result<UnixClock, Error> {
+        #[cfg(target_os = "linux")]
+        match &self.fd {
+            Some(ClockFd::Owned(fd)) => {
+                return Ok(Self {
+                    clock: self.clock,
+                    fd: Some(ClockFd::Owned(fd.clone())),
+                    cache: self.cache.clone(),
+                    frequency_only: self.frequency_only,
+                })
+            }
+            Some(ClockFd::Borrowed(fd)) => {
+                // # Safety
+                //
+                // The fd stays open for the lifetime of the `BorrowedUnixClock` that we are part of
+                let fd = unsafe { BorrowedFd::borrow_raw(*fd) }
+                    .try_clone_to_owned()
+                    .map_err(error_from_io)?;
+
+                let mut clock = Self::from_owned_fd(fd);
+                clock.cache = self.cache.clone();
+                clock.frequency_only = self.frequency_only;
+
+                return Ok(clock);
+            }
+            None => {}
+        }
+
+        Ok(Self {
+            clock: self.clock,
+            #[cfg(target_os = "linux")]
+            fd: None,
+            #[cfg(target_os = "linux")]
+            cache: None,
+            frequency_only: self.frequency_only,
+        })
+    }
+
+    /// The `max_adj` capability of a clock device, if it has been read already
+    #[cfg(target_os = "linux")]
+    fn cached_max_adj(&self) -> Option<i32> {
+        let cache = self.cache.as_ref()?;
+
+        // pairs with the release store in `frequency_limit`, so that `max_adj` is visible
+        cache
+            .max_adj_read
+            .load(Ordering::Acquire)
+            .then(|| cache.max_adj.load(Ordering::Relaxed))
+    }
+
+    /// The largest frequency adjustment that the clock accepts, in units of 2^-16 ppm.
+    ///
+    /// For clock devices this is derived from their `max_adj` capability, which is read once and
+    /// then cached. Other clocks are limited by the NTP kernel API.
+    fn frequency_limit(&self) -> Result<i64, Error> {
+        #[cfg(target_os = "linux")]
+        if let Some(cache) = &self.cache {
+            let max_adj = match self.cached_max_adj() {
+                Some(max_adj) => max_adj,
+                None => {
+                    let max_adj = self.capabilities()?.max_adj;
+                    cache.max_adj.store(max_adj, Ordering::Relaxed);
+                    cache.max_adj_read.store(true, Ordering::Release);
+                    max_adj
+                }
+            };
+
+            return Ok(Self::scaled_frequency_limit(max_adj));
+        }
+
+        Ok(NTP_KAPI_MAX_FREQUENCY)
+    }
+
+    /// Convert a `max_adj` in ppb to units of 2^-16 ppm, rounding down so that the device never
+    /// sees a frequency beyond its limit.
+    #[cfg(target_os = "linux")]
+    fn scaled_frequency_limit(max_adj: i32) -> i64 {
+        // c_long is only 32 bits on some platforms
+        #[allow(clippy::useless_conversion)]
+        let c_long_max = i64::from(libc::c_long::MAX);
+
+        (i64::from(max_adj) * 65536 / 1000).clamp(0, c_long_max)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn raw_fd(&self) -> Option<RawFd> {
+        match self.fd.as_ref()? {
+            ClockFd::Owned(fd) => Some(fd.as_raw_fd()),
+            ClockFd::Borrowed(fd) => Some(*fd),
         }
     }
 
@@ -100,7 +697,7 @@ impl UnixClock {
     pub fn system_offset(&self) -> Result<(Timestamp, Timestamp, Timestamp), Error> {
         use libc::{ptp_clock_time, ptp_sys_offset, PTP_SYS_OFFSET};
 
-        let Some(fd) = self.fd else {
+        let Some(fd) = self.raw_fd() else {
             return Err(Error::Invalid);
         };
 
@@ -145,658 +742,5974 @@ impl UnixClock {
         }
     }
 
-    fn clock_adjtime(&self, timex: &mut libc::timex) -> Result<(), Error> {
-        // We don't care about the time status, so the non-error
-        // information in the return value of clock_adjtime can be ignored.
-        //
+    /// Determine offset between file clock and TAI clock (if any), using multiple samples.
+    ///
+    /// For each sample, returns two system timestamps sandwiching a timestamp from the hardware
+    /// clock. The sample with the smallest interval between the system timestamps gives the best
+    /// estimate of the offset. `n_samples` is clamped to the kernel's limit of
+    /// [`libc::PTP_MAX_SAMPLES`].
+    #[cfg(target_os = "linux")]
+    pub fn system_offset_extended(
+        &self,
+        n_samples: u32,
+    ) -> Result<Vec<(Timestamp, Timestamp, Timestamp)>, Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
+
         // # Safety
         //
-        // The clock_adjtime call is safe because the reference always
-        // points to a valid libc::timex.
+        // ptp_sys_offset_extended only contains integers, for which all zeroes is a valid value
+        let mut offset: libc::ptp_sys_offset_extended = unsafe { std::mem::zeroed() };
+        offset.n_samples = Self::clamp_extended_samples(n_samples);
+
+        // # Safety
         //
-        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
-        // error https://man.archlinux.org/man/clock_adjtime.2.en#EINVAL~4
-        #[cfg(target_os = "linux")]
-        use libc::clock_adjtime as adjtime;
+        // PTP_SYS_OFFSET_EXTENDED receives a valid ptp_sys_offset_extended mutable pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_SYS_OFFSET_EXTENDED as _, &mut offset) })?;
 
-        #[cfg(any(target_os = "freebsd", target_os = "macos"))]
-        unsafe fn adjtime(clk_id: libc::clockid_t, buf: *mut libc::timex) -> libc::c_int {
-            assert_eq!(
-                clk_id,
-                libc::CLOCK_REALTIME,
-                "only the REALTIME clock is supported"
-            );
+        let tai_offset = Self::CLOCK_TAI.get_tai()?;
 
-            libc::ntp_adjtime(buf)
+        Ok(Self::extended_samples(&offset, tai_offset))
+    }
+
+    /// Measure the offset between a clock device and the system clock with
+    /// [`UnixClock::system_offset`], which reads both clocks in the kernel. Pass it to
+    /// [`Synchronizer::with_measure`](crate::synchronizer::Synchronizer::with_measure) when one
+    /// of the clocks is a device, and the other is [`UnixClock::CLOCK_REALTIME`] or
+    /// [`UnixClock::CLOCK_TAI`]. Other pairs of clocks are measured by reading them, like
+    /// [`measure_offset`](crate::synchronizer::measure_offset).
+    #[cfg(target_os = "linux")]
+    pub fn measure_system_offset(
+        reference: &UnixClock,
+        target: &UnixClock,
+    ) -> Result<(Timestamp, SignedDuration), SyncError<Error, Error>> {
+        if target.fd.is_some() && reference.is_system_clock() {
+            let tai_offset = reference
+                .system_tai_offset()
+                .map_err(SyncError::Reference)?;
+            let (device, system) = target
+                .device_and_system_time(tai_offset)
+                .map_err(SyncError::Target)?;
+
+            return Ok((device, device.offset_to(&system)));
         }
 
-        if unsafe { adjtime(self.clock, timex) } == -1 {
-            Err(convert_errno())
-        } else {
-            Ok(())
+        if reference.fd.is_some() && target.is_system_clock() {
+            let tai_offset = target.system_tai_offset().map_err(SyncError::Target)?;
+            let (device, system) = reference
+                .device_and_system_time(tai_offset)
+                .map_err(SyncError::Reference)?;
+
+            return Ok((system, system.offset_to(&device)));
         }
+
+        crate::synchronizer::measure_offset(reference, target)
     }
 
-    fn ntp_adjtime(timex: &mut libc::timex) -> Result<(), Error> {
-        #[cfg(any(target_os = "freebsd", target_os = "macos", target_env = "gnu"))]
-        use libc::ntp_adjtime as adjtime;
+    #[cfg(target_os = "linux")]
+    fn is_system_clock(&self) -> bool {
+        self.fd.is_none() && (self.clock == libc::CLOCK_REALTIME || self.clock == libc::CLOCK_TAI)
+    }
 
-        // ntp_adjtime is equivalent to adjtimex for our purposes
-        //
-        // https://man7.org/linux/man-pages/man2/adjtimex.2.html
-        #[cfg(all(target_os = "linux", target_env = "musl"))]
-        use libc::adjtimex as adjtime;
+    /// The offset of this system clock to the TAI timescale of [`UnixClock::system_offset`]
+    #[cfg(target_os = "linux")]
+    fn system_tai_offset(&self) -> Result<i32, Error> {
+        match self.clock {
+            libc::CLOCK_REALTIME => self.get_tai(),
+            _ => Ok(0),
+        }
+    }
 
-        // We don't care about the time status, so the non-error
-        // information in the return value of ntp_adjtime can be ignored.
-        // The ntp_adjtime call is safe because the reference always
-        // points to a valid libc::timex.
-        if unsafe { adjtime(timex) } == -1 {
-            Err(convert_errno())
-        } else {
-            Ok(())
+    /// The time of this device, and the time of the system clock halfway between the reads
+    /// around it, `tai_offset` seconds behind TAI
+    #[cfg(target_os = "linux")]
+    fn device_and_system_time(&self, tai_offset: i32) -> Result<(Timestamp, Timestamp), Error> {
+        let (before, device, after) = self.system_offset()?;
+
+        Ok((device, Self::system_midpoint(before, after, tai_offset)))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn system_midpoint(before: Timestamp, after: Timestamp, tai_offset: i32) -> Timestamp {
+        let elapsed = after.duration_since(&before).unwrap_or_default();
+        let midpoint = before + elapsed / 2;
+
+        Timestamp {
+            seconds: midpoint.seconds - crate::time_t::from(tai_offset),
+            ..midpoint
         }
     }
 
-    /// Adjust the clock state with a [`libc::timex`] specifying the desired changes.
-    ///
-    /// This is a lowlevel function. If possible, use more specialized (trait) methods.
+    #[cfg(target_os = "linux")]
+    fn clamp_extended_samples(n_samples: u32) -> u32 {
+        n_samples.clamp(1, libc::PTP_MAX_SAMPLES)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn extended_samples(
+        offset: &libc::ptp_sys_offset_extended,
+        tai_offset: i32,
+    ) -> Vec<(Timestamp, Timestamp, Timestamp)> {
+        let system_time = |time: &libc::ptp_clock_time| Timestamp {
+            seconds: (time.sec + tai_offset as i64) as _,
+            nanos: time.nsec,
+        };
+
+        offset.ts[..offset.n_samples as usize]
+            .iter()
+            .map(|[before, device, after]| {
+                (
+                    system_time(before),
+                    timestamp_from_ptp_clock_time(device),
+                    system_time(after),
+                )
+            })
+            .collect()
+    }
+
+    /// The shortest interval that [`UnixClock::estimate_frequency_ratio`] accepts. Over shorter
+    /// intervals, the uncertainty of the offset measurements dominates the slope.
+    #[cfg(target_os = "linux")]
+    pub const MIN_FREQUENCY_RATIO_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Estimate the frequency of the clock device relative to the system clock, from two
+    /// [`UnixClock::system_offset`] measurements `interval` apart. A ratio above 1 means that the
+    /// device runs fast.
     ///
-    /// Note that [`libc::timex`] has a different layout between different operating systems, and
-    /// not all fields are available on all operating systems. Keep this in mind when writing
-    /// platform-independent code.
-    fn adjtime(&self, timex: &mut libc::timex) -> Result<(), Error> {
-        if self.clock == libc::CLOCK_REALTIME {
-            Self::ntp_adjtime(timex)
-        } else {
-            self.clock_adjtime(timex)
+    /// This blocks for `interval`. Longer intervals give more precise estimates. Returns
+    /// [`Error::Invalid`] for intervals shorter than
+    /// [`UnixClock::MIN_FREQUENCY_RATIO_INTERVAL`], and for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn estimate_frequency_ratio(&self, interval: Duration) -> Result<f64, Error> {
+        if interval < Self::MIN_FREQUENCY_RATIO_INTERVAL {
+            return Err(Error::Invalid);
         }
+
+        let first = self.system_offset()?;
+        std::thread::sleep(interval);
+        let second = self.system_offset()?;
+
+        frequency_ratio(first, second).ok_or(Error::Invalid)
     }
 
-    #[cfg_attr(target_os = "linux", allow(unused))]
-    fn clock_gettime(&self) -> Result<libc::timespec, Error> {
-        let mut timespec = EMPTY_TIMESPEC;
+    /// Determine the offset between the clock device and the system clocks using hardware
+    /// cross-timestamping, which is much more precise than [`UnixClock::system_offset`].
+    ///
+    /// Returns the device time, and the simultaneous `CLOCK_REALTIME` and `CLOCK_MONOTONIC_RAW`
+    /// times. Devices without cross-timestamping support return [`Error::OperationUnsupported`], in which
+    /// case [`UnixClock::system_offset`] can be used instead.
+    #[cfg(target_os = "linux")]
+    pub fn system_offset_precise(&self) -> Result<(Timestamp, Timestamp, Timestamp), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
         // # Safety
         //
-        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
-        // error https://linux.die.net/man/3/clock_gettime
+        // ptp_sys_offset_precise only contains integers, for which all zeroes is a valid value
+        let mut offset: libc::ptp_sys_offset_precise = unsafe { std::mem::zeroed() };
+
+        // # Safety
         //
-        // The timespec pointer is valid.
-        cerr(unsafe { libc::clock_gettime(self.clock, &mut timespec) })?;
+        // PTP_SYS_OFFSET_PRECISE receives a valid ptp_sys_offset_precise mutable pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_SYS_OFFSET_PRECISE as _, &mut offset) })?;
 
-        Ok(timespec)
+        Ok((
+            timestamp_from_ptp_clock_time(&offset.device),
+            timestamp_from_ptp_clock_time(&offset.sys_realtime),
+            timestamp_from_ptp_clock_time(&offset.sys_monoraw),
+        ))
     }
 
-    #[cfg_attr(target_os = "linux", allow(unused))]
-    fn clock_settime(&self, mut timespec: libc::timespec) -> Result<(), Error> {
-        while timespec.tv_nsec > 1_000_000_000 {
-            timespec.tv_sec += 1;
-            timespec.tv_nsec -= 1_000_000_000;
-        }
+    /// Enable (or disable) timestamping of external events on a channel of a clock device.
+    ///
+    /// The events can be read with [`UnixClock::read_external_timestamp`]. Returns
+    /// [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn enable_external_timestamp(&self, channel: u32, flags: ExtTsFlags) -> Result<(), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
+
+        let request = libc::ptp_extts_request {
+            index: channel,
+            flags: flags.as_raw(),
+            rsv: [0; 2],
+        };
 
         // # Safety
         //
-        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
-        // error https://linux.die.net/man/3/clock_settime
-        //
-        // The timespec pointer is valid.
-        unsafe { cerr(libc::clock_settime(self.clock, &timespec))? };
-
-        Ok(())
+        // PTP_EXTTS_REQUEST2 receives a valid ptp_extts_request pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_EXTTS_REQUEST2 as _, &request) })
     }
 
-    #[cfg_attr(target_os = "linux", allow(unused))]
-    fn step_clock_by_timespec(&self, offset: TimeOffset) -> Result<Timestamp, Error> {
-        let mut timespec = self.clock_gettime()?;
+    /// Read the next external timestamp event from a clock device. This blocks until an event is
+    /// available.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn read_external_timestamp(&self) -> Result<PtpExtTsEvent, Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-        // see https://github.com/rust-lang/libc/issues/1848
-        #[cfg_attr(target_env = "musl", allow(deprecated))]
-        {
-            timespec.tv_sec += offset.seconds as libc::time_t;
-            timespec.tv_nsec += offset.nanos as libc::c_long;
-        }
+        loop {
+            if let Some(event) = try_read_external_timestamp(fd)? {
+                return Ok(event);
+            }
 
-        self.clock_settime(timespec)?;
+            // the fd is non-blocking (e.g. it is used by an `AsyncUnixClock`), so wait for an
+            // event to arrive
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
 
-        Ok(current_time_timespec(timespec, Precision::Nano))
+            // # Safety
+            //
+            // We pass a single, valid pollfd
+            match unsafe { libc::poll(&mut pollfd, 1, -1) } {
+                -1 if error_number() == libc::EINTR => continue,
+                -1 => return Err(convert_errno()),
+                _ => continue,
+            }
+        }
     }
 
-    fn error_estimate_timex(est_error: Duration, max_error: Duration) -> libc::timex {
-        let modes = libc::MOD_ESTERROR | libc::MOD_MAXERROR;
-
-        // these fields are always in microseconds
-        let esterror = est_error.as_nanos() as libc::c_long / 1000;
-        let maxerror = max_error.as_nanos() as libc::c_long / 1000;
+    /// Read up to `max` queued external timestamp events from a clock device, oldest first.
+    ///
+    /// This stops as soon as the queue is empty, which requires the file descriptor to be
+    /// non-blocking (see [`UnixClock::set_nonblocking`]). On a blocking file descriptor, this
+    /// waits until `max` events have arrived.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn drain_external_timestamps(&self, max: usize) -> Result<Vec<PtpExtTsEvent>, Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-        libc::timex {
-            modes,
-            esterror,
-            maxerror,
-            ..EMPTY_TIMEX
-        }
+        drain_events(max, || try_read_external_timestamp(fd))
     }
 
+    /// Switch the file descriptor of a clock device between blocking and non-blocking mode,
+    /// which determines whether reading external timestamps waits for an event.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
     #[cfg(target_os = "linux")]
-    fn step_clock_timex(offset: TimeOffset) -> libc::timex {
-        // we provide the offset in nanoseconds
-        let modes = libc::ADJ_SETOFFSET | libc::ADJ_NANO;
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-        let time = libc::timeval {
-            tv_sec: offset.seconds,
-            tv_usec: offset.nanos as libc::suseconds_t,
+        set_fd_nonblocking(fd, nonblocking)
+    }
+
+    /// Wait up to `timeout` for the next external timestamp event (e.g. a PPS edge) of a clock
+    /// device, and return its time. Returns [`Error::TimedOut`] when no event arrives in time.
+    ///
+    /// When other readers share the file descriptor (e.g. clones of this clock), the fd must be
+    /// non-blocking (see [`UnixClock::set_nonblocking`]): another reader may take the event
+    /// between the wait and the read, and a blocking read would then wait beyond `timeout`.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn wait_for_pps(&self, timeout: Duration) -> Result<Timestamp, Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
         };
 
-        libc::timex {
-            modes,
-            time,
-            ..EMPTY_TIMEX
+        let deadline = std::time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if !poll_readable(fd, remaining)? {
+                return Err(Error::TimedOut);
+            }
+
+            // another reader may have taken the event, then keep waiting
+            if let Some(event) = try_read_external_timestamp(fd)? {
+                return Ok(event.timestamp);
+            }
         }
     }
 
+    /// Configure a periodic output signal on a channel of a clock device.
+    ///
+    /// The signal starts at `start` (or, with [`PerOutFlags::phase`], at the given offset into
+    /// each period) and repeats every `period`. A zero `period` disables the output. Returns
+    /// [`Error::Invalid`] for clocks that are not a device.
     #[cfg(target_os = "linux")]
-    fn step_clock_by_timex(&self, offset: TimeOffset) -> Result<Timestamp, Error> {
-        let mut timex = Self::step_clock_timex(offset);
-        self.adjtime(&mut timex)?;
-        self.extract_current_time(&timex)
+    pub fn configure_periodic_output(
+        &self,
+        channel: u32,
+        start: Timestamp,
+        period: Duration,
+        flags: PerOutFlags,
+    ) -> Result<(), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
+
+        let request = Self::perout_request(channel, start, period, flags);
+
+        // # Safety
+        //
+        // PTP_PEROUT_REQUEST2 receives a valid ptp_perout_request pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_PEROUT_REQUEST2 as _, &request) })
     }
 
-    fn extract_current_time(&self, _timex: &libc::timex) -> Result<Timestamp, Error> {
-        #[cfg(target_os = "linux")]
-        // hardware clocks may not report the timestamp
-        if _timex.time.tv_sec != 0 && _timex.time.tv_usec != 0 {
-            // in a timex, the status flag determines precision
-            let precision = match _timex.status & libc::STA_NANO {
-                0 => Precision::Micro,
-                _ => Precision::Nano,
-            };
+    #[cfg(target_os = "linux")]
+    fn perout_request(
+        channel: u32,
+        start: Timestamp,
+        period: Duration,
+        flags: PerOutFlags,
+    ) -> libc::ptp_perout_request {
+        let start = ptp_clock_time(start.seconds as _, start.nanos);
 
-            return Ok(current_time_timeval(_timex.time, precision));
-        }
+        let on = match flags.duty_cycle {
+            Some(on) => libc::__c_anonymous_ptp_perout_request_2 {
+                on: ptp_clock_time(on.as_secs() as _, on.subsec_nanos()),
+            },
+            None => libc::__c_anonymous_ptp_perout_request_2 { rsv: [0; 4] },
+        };
 
-        // clock_gettime always gives nanoseconds
-        let timespec = self.clock_gettime()?;
-        Ok(current_time_timespec(timespec, Precision::Nano))
+        libc::ptp_perout_request {
+            // start and phase share their representation
+            anonymous_1: libc::__c_anonymous_ptp_perout_request_1 { start },
+            period: ptp_clock_time(period.as_secs() as _, period.subsec_nanos()),
+            index: channel,
+            flags: flags.as_raw(),
+            anonymous_2: on,
+        }
     }
 
-    #[inline(always)]
-    fn update_timex<F>(&self, f: F) -> Result<(), Error>
-    where
-        F: FnOnce(libc::timex) -> libc::timex,
-    {
-        let mut timex = EMPTY_TIMEX;
-        self.adjtime(&mut timex)?;
+    /// Assign a function to a pin of a clock device, where `channel` is the external timestamp or
+    /// periodic output channel that the pin is routed to.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn set_pin_function(
+        &self,
+        pin_index: u32,
+        func: PinFunction,
+        channel: u32,
+    ) -> Result<(), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-        timex = f(timex);
+        let mut desc = empty_ptp_pin_desc(pin_index);
+        desc.func = func.as_raw();
+        desc.chan = channel;
 
-        self.adjtime(&mut timex)
+        // # Safety
+        //
+        // PTP_PIN_SETFUNC2 receives a valid ptp_pin_desc pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_PIN_SETFUNC2 as _, &desc) })
     }
 
-    #[inline(always)]
-    fn update_status<F>(&self, f: F) -> Result<(), Error>
-    where
-        F: FnOnce(libc::c_int) -> libc::c_int,
-    {
-        self.update_timex(|mut timex| {
-            // We are setting the status bits
-            timex.modes = libc::MOD_STATUS;
+    /// Read the function of a pin of a clock device, and the channel it is routed to.
+    ///
+    /// Returns [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn get_pin_function(&self, pin_index: u32) -> Result<(PinFunction, u32), Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-            // update the status flags
-            timex.status = f(timex.status);
+        let mut desc = empty_ptp_pin_desc(pin_index);
 
-            timex
-        })
+        // # Safety
+        //
+        // PTP_PIN_GETFUNC2 receives a valid ptp_pin_desc mutable pointer
+        cerr(unsafe { libc::ioctl(fd, libc::PTP_PIN_GETFUNC2 as _, &mut desc) })?;
+
+        let func = PinFunction::from_raw(desc.func).ok_or(Error::Invalid)?;
+
+        Ok((func, desc.chan))
     }
 
-    fn set_frequency_timex(ppm: f64) -> libc::timex {
-        // We do an offset with precision
-        let mut timex = EMPTY_TIMEX;
+    /// The name the driver gives this clock device, e.g. `e1000e PTP`.
+    ///
+    /// The `ptp_clock_caps` of the kernel carry no name, so it is read from sysfs. Returns
+    /// [`Error::Invalid`] for clocks that are not a device.
+    #[cfg(target_os = "linux")]
+    pub fn device_name(&self) -> Result<String, Error> {
+        let Some(fd) = self.raw_fd() else {
+            return Err(Error::Invalid);
+        };
 
-        // set the frequency (MOD_FREQUENCY is an alias for ADJ_FREQUENCY on linux)
-        timex.modes = libc::MOD_FREQUENCY;
+        // # Safety
+        //
+        // stat only contains integers, for which all zeroes is a valid value
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
 
-        // NTP Kapi expects frequency adjustment in units of 2^-16 ppm
-        // but our input is in units of seconds drift per second, so convert.
-        let frequency = (ppm * 65536.0).round() as libc::c_long;
+        // # Safety
+        //
+        // fstat receives a valid stat mutable pointer
+        cerr(unsafe { libc::fstat(fd, &mut stat) })?;
 
-        // Since Linux 2.6.26, the supplied value is clamped to the range (-32768000,
-        // +32768000). In older kernels, an EINVAL error occurs if the supplied value is
-        // out of range. (32768000 is 500 << 16)
-        timex.freq = frequency.clamp(-32_768_000 + 1, 32_768_000 - 1);
+        let (major, minor) = (libc::major(stat.st_rdev), libc::minor(stat.st_rdev));
+        let path = format!("/sys/dev/char/{major}:{minor}/clock_name");
 
-        timex
+        decode_device_name(&std::fs::read(path).map_err(error_from_io)?)
     }
-}
-
-impl Clock for UnixClock {
-    type Error = Error;
 
-    fn now(&self) -> Result<Timestamp, Self::Error> {
-        let mut ntp_kapi_timex = EMPTY_TIMEX;
+    /// Run a steering operation, and measure how long it took with the monotonic clock. This
+    /// latency bounds the uncertainty of the time at which the change was applied.
+    ///
+    /// ```no_run
+    /// use clock_steering::{Clock, unix::UnixClock};
+    ///
+    /// let clock = UnixClock::CLOCK_REALTIME;
+    /// let (applied, latency) = clock.timed_adjust(|clock| clock.set_frequency(1.5))?;
+    ///
+    /// println!("applied at {applied:?}, took {latency:?}");
+    /// # Ok::<(), clock_steering::unix::Error>(())
+    /// ```
+    pub fn timed_adjust<T>(
+        &self,
+        operation: impl FnOnce(&Self) -> Result<T, Error>,
+    ) -> Result<(T, Duration), Error> {
+        let before = std::time::Instant::now();
+        let value = operation(self)?;
 
-        if self.adjtime(&mut ntp_kapi_timex).is_ok() {
-            self.extract_current_time(&ntp_kapi_timex)
-        } else {
-            self.clock_gettime()
-                .map(|ts| current_time_timespec(ts, Precision::Nano))
-        }
+        Ok((value, before.elapsed()))
     }
 
-    fn resolution(&self) -> Result<Timestamp, Self::Error> {
-        let mut timespec = EMPTY_TIMESPEC;
+    /// Read the clock `samples` times, and return the reading that took the least time.
+    ///
+    /// The time each read takes is measured with the monotonic clock, and is returned as the
+    /// uncertainty of the reading. Returns [`Error::Invalid`] when `samples` is zero.
+    pub fn now_sampled(&self, samples: usize) -> Result<SampledTime, Error> {
+        let mut best: Option<SampledTime> = None;
 
-        cerr(unsafe { libc::clock_getres(self.clock, &mut timespec) })?;
+        for _ in 0..samples {
+            let before = std::time::Instant::now();
+            let timestamp = self.now()?;
+            let uncertainty = before.elapsed();
 
-        Ok(current_time_timespec(timespec, Precision::Nano))
-    }
+            if best.map_or(true, |best| uncertainty < best.uncertainty) {
+                best = Some(SampledTime {
+                    timestamp,
+                    uncertainty,
+                });
+            }
+        }
 
-    fn get_frequency(&self) -> Result<f64, Self::Error> {
-        let mut timex = EMPTY_TIMEX;
-        self.adjtime(&mut timex)?;
+        best.ok_or(Error::Invalid)
+    }
 
-        Ok((timex.freq as f64) / 65536.0)
+    /// Whether [`Clock::get_tai`] and [`Clock::set_tai`] are supported on this platform.
+    ///
+    /// | Platform | TAI offset                                           |
+    /// |----------|------------------------------------------------------|
+    /// | Linux    | all steerable clocks (hardware clocks report 0)      |
+    /// | FreeBSD  | [`UnixClock::CLOCK_REALTIME`], with positive offsets |
+    /// | macOS    | not supported                                        |
+    pub const fn supports_tai() -> bool {
+        cfg!(any(target_os = "linux", target_os = "freebsd"))
     }
 
-    fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
-        let mut timex = Self::set_frequency_timex(frequency);
-        self.adjtime(&mut timex)?;
-        self.extract_current_time(&timex)
+    /// Read the TAI offset together with the leap second state of the clock, from a single
+    /// kernel call. The state tells whether a leap second is pending or in progress.
+    ///
+    /// Like [`UnixClock::supports_tai`], this is only supported on Linux, and on FreeBSD for
+    /// [`UnixClock::CLOCK_REALTIME`]. Hardware clocks have no TAI offset, and report 0. Where it
+    /// is not supported, including for the monotonic clocks, this returns
+    /// [`Error::NotSupported`].
+    pub fn tai_info(&self) -> Result<TaiInfo, Error> {
+        self.read_tai_info()
     }
 
     #[cfg(target_os = "linux")]
-    fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
-        self.step_clock_by_timex(offset)
-    }
+    fn read_tai_info(&self) -> Result<TaiInfo, Error> {
+        // the monotonic clocks have no TAI offset, on every kernel
+        self.ensure_steerable()?;
 
-    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+        let mut timex = EMPTY_TIMEX;
+        let state = self.clock_adjtime(&mut timex)?;
+
+        Ok(TaiInfo {
+            tai: timex.tai,
+            state: ClockState::from_raw(state),
+        })
+    }
+
+    #[cfg(target_os = "freebsd")]
+    fn read_tai_info(&self) -> Result<TaiInfo, Error> {
+        // only the kernel clock keeps a TAI offset
+        if self.clock != libc::CLOCK_REALTIME {
+            return Err(Error::NotSupported);
+        }
+
+        // # Safety
+        //
+        // ntptimeval only contains integers, for which all zeroes is a valid value
+        let mut ntv: libc::ntptimeval = unsafe { std::mem::zeroed() };
+
+        // # Safety
+        //
+        // ntp_gettime receives a valid ntptimeval mutable pointer
+        match unsafe { libc::ntp_gettime(&mut ntv) } {
+            -1 => Err(convert_errno()),
+            state => Ok(TaiInfo {
+                tai: ntv.tai as _,
+                state: ClockState::from_raw(state),
+            }),
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+    fn read_tai_info(&self) -> Result<TaiInfo, Error> {
+        Err(Error::NotSupported)
+    }
+
+    /// Never step this clock: [`Clock::step_clock`] (and everything built on it) returns
+    /// [`Error::NotSupported`], and [`UnixClock::correct_offset`] always slews. This prevents
+    /// accidental discontinuities where stepping the clock is not allowed. Offsets beyond
+    /// [`UnixClock::MAX_SLEW`] cannot be slewed, so correcting those fails with
+    /// [`Error::Invalid`].
+    ///
+    /// Clones of the returned clock are frequency-only as well.
+    pub fn with_frequency_only(mut self) -> Self {
+        self.frequency_only = true;
+        self
+    }
+
+    /// Whether this clock refuses steps, see [`UnixClock::with_frequency_only`]
+    pub fn is_frequency_only(&self) -> bool {
+        self.frequency_only
+    }
+
+    /// The clock id to pass to libc calls that this crate does not wrap, e.g.
+    /// `clock_nanosleep` or `timerfd_create`. For the kernel clocks this is the libc constant,
+    /// for clock devices the dynamic clock id derived from their file descriptor, which is only
+    /// valid while this clock is alive.
+    ///
+    /// Steering the clock through this id bypasses the checks of this crate, such as
+    /// [`UnixClock::with_frequency_only`] and the frequency limit of clock devices.
+    pub fn raw_clock_id(&self) -> libc::clockid_t {
+        self.clock
+    }
+
+    fn ensure_may_step(&self) -> Result<(), Error> {
+        match self.frequency_only {
+            true => Err(Error::NotSupported),
+            false => Ok(()),
+        }
+    }
+
+    /// Which operations this clock supports, derived from the kind of clock. Operations that are
+    /// not supported return [`Error::NotSupported`] (or are silently ignored, see
+    /// [`SupportedOps`]).
+    pub fn supported_operations(&self) -> SupportedOps {
+        let operations = self.supported_operations_by_kind();
+
+        SupportedOps {
+            step: operations.step && !self.frequency_only,
+            ..operations
+        }
+    }
+
+    fn supported_operations_by_kind(&self) -> SupportedOps {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            return SupportedOps::DEVICE;
+        }
+
+        match self.clock {
+            libc::CLOCK_REALTIME => SupportedOps {
+                tai: Self::supports_tai(),
+                ..SupportedOps::KERNEL
+            },
+            // monotonic clocks and the TAI clock can only be read, the TAI clock is steered
+            // through the realtime clock
+            _ => SupportedOps::READ_ONLY,
+        }
+    }
+
+    /// Check whether the clock can still be read. This is `false` when a clock device has gone
+    /// away, e.g. because a USB network card was unplugged, in which case it should be re-opened.
+    pub fn is_alive(&self) -> bool {
+        !matches!(self.clock_gettime(), Err(Error::NoDevice))
+    }
+
+    /// Check, without changing anything, whether this process may steer the clock.
+    ///
+    /// - A clock device can be steered when its file descriptor is open for writing.
+    /// - On Linux, the kernel clocks require `CAP_SYS_TIME`, which is looked up in the
+    ///   effective capabilities listed in `/proc/self/status`.
+    /// - Elsewhere, or when `/proc` is not available, the kernel clocks require root.
+    ///
+    /// Clocks that cannot be steered at all, such as [`UnixClock::CLOCK_MONOTONIC`], report
+    /// `false`. A `true` result is no guarantee: e.g. a seccomp filter may still refuse a change.
+    pub fn can_steer(&self) -> bool {
+        if self.ensure_steerable().is_err() {
+            return false;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = self.raw_fd() {
+            // # Safety
+            //
+            // fcntl with F_GETFL has no memory safety preconditions
+            let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+            return flags != -1 && flags & libc::O_ACCMODE != libc::O_RDONLY;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(has_cap) = std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| has_cap_sys_time(&status))
+        {
+            return has_cap;
+        }
+
+        // # Safety
+        //
+        // geteuid has no preconditions, and cannot fail
+        unsafe { libc::geteuid() == 0 }
+    }
+
+    /// Read the resolution of the clock from the kernel, bypassing the cache that
+    /// [`Clock::resolution`] uses.
+    pub fn resolution_uncached(&self) -> Result<Timestamp, Error> {
+        let mut timespec = EMPTY_TIMESPEC;
+
+        // # Safety
+        //
+        // using an invalid clock id is safe. `clock_getres` will return an EINVAL
+        // error https://linux.die.net/man/3/clock_getres
+        //
+        // The timespec pointer is valid.
+        cerr(unsafe { libc::clock_getres(self.clock, &mut timespec) })?;
+
+        Ok(current_time_timespec(timespec, Precision::Nano))
+    }
+
+    /// The resolution from the cache, or from `read` when it has not been read before. Clock
+    /// devices have a cache that their clones share, each kernel clock has a static one. Other
+    /// clocks are not cached.
+    fn cached_resolution(
+        &self,
+        read: impl FnOnce() -> Result<Timestamp, Error>,
+    ) -> Result<Timestamp, Error> {
+        #[cfg(target_os = "linux")]
+        if let Some(cache) = &self.cache {
+            return cached_resolution_in(&cache.resolution_nanos, read);
+        }
+
+        match kernel_resolution_cache(self.clock) {
+            Some(cache) => cached_resolution_in(cache, read),
+            None => read(),
+        }
+    }
+
+    /// Determine the capabilities of this clock.
+    ///
+    /// For clock devices these are read with the `PTP_CLOCK_GETCAPS` ioctl. Other clocks report
+    /// the limits of the NTP kernel API.
+    pub fn capabilities(&self) -> Result<Capabilities, Error> {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = self.raw_fd() {
+            // # Safety
+            //
+            // ptp_clock_caps only contains integers, for which all zeroes is a valid value
+            let mut caps: libc::ptp_clock_caps = unsafe { std::mem::zeroed() };
+
+            // # Safety
+            //
+            // PTP_CLOCK_GETCAPS receives a valid ptp_clock_caps mutable pointer
+            cerr(unsafe { libc::ioctl(fd, libc::PTP_CLOCK_GETCAPS as _, &mut caps) })?;
+
+            return Ok(Capabilities::from_ptp_clock_caps(&caps));
+        }
+
+        Ok(Capabilities::NTP_KAPI)
+    }
+
+    /// The non-error return value is the state of the clock, see [`ClockState`].
+    fn clock_adjtime(&self, timex: &mut libc::timex) -> Result<libc::c_int, Error> {
+        // # Safety
+        //
+        // The clock_adjtime call is safe because the reference always
+        // points to a valid libc::timex.
+        //
+        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
+        // error https://man.archlinux.org/man/clock_adjtime.2.en#EINVAL~4
+        #[cfg(target_os = "linux")]
+        use libc::clock_adjtime as adjtime;
+
+        #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+        unsafe fn adjtime(clk_id: libc::clockid_t, buf: *mut libc::timex) -> libc::c_int {
+            assert_eq!(
+                clk_id,
+                libc::CLOCK_REALTIME,
+                "only the REALTIME clock is supported"
+            );
+
+            libc::ntp_adjtime(buf)
+        }
+
+        match unsafe { adjtime(self.clock, timex) } {
+            -1 => Err(convert_errno()),
+            state => Ok(state),
+        }
+    }
+
+    /// The non-error return value is the state of the clock, see [`ClockState`].
+    fn ntp_adjtime(timex: &mut libc::timex) -> Result<libc::c_int, Error> {
+        #[cfg(any(target_os = "freebsd", target_os = "macos", target_env = "gnu"))]
+        use libc::ntp_adjtime as adjtime;
+
+        // ntp_adjtime is equivalent to adjtimex for our purposes
+        //
+        // https://man7.org/linux/man-pages/man2/adjtimex.2.html
+        #[cfg(all(target_os = "linux", target_env = "musl"))]
+        use libc::adjtimex as adjtime;
+
+        // The ntp_adjtime call is safe because the reference always
+        // points to a valid libc::timex.
+        match unsafe { adjtime(timex) } {
+            -1 => Err(convert_errno()),
+            state => Ok(state),
+        }
+    }
+
+    /// Adjust the clock state with a [`libc::timex`] specifying the desired changes.
+    ///
+    /// This is a lowlevel function. If possible, use more specialized (trait) methods.
+    ///
+    /// Note that [`libc::timex`] has a different layout between different operating systems, and
+    /// not all fields are available on all operating systems. Keep this in mind when writing
+    /// platform-independent code.
+    fn adjtime(&self, timex: &mut libc::timex) -> Result<(), Error> {
+        self.adjtime_with_state(timex).map(|_| ())
+    }
+
+    /// Like [`UnixClock::adjtime`], but also returns the state of the clock after the change.
+    fn adjtime_with_state(&self, timex: &mut libc::timex) -> Result<ClockState, Error> {
+        self.ensure_steerable()?;
+
+        let state = if self.clock == libc::CLOCK_REALTIME {
+            Self::ntp_adjtime(timex)?
+        } else {
+            self.clock_adjtime(timex)?
+        };
+
+        Ok(ClockState::from_raw(state))
+    }
+
+    /// Read the leap second state of this clock. The kernel moves through these states as it
+    /// inserts or deletes a leap second announced with [`Clock::set_leap_seconds`].
+    ///
+    /// Hardware clocks do not handle leap seconds, and always report [`ClockState::Ok`].
+    pub fn clock_state(&self) -> Result<ClockState, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime_with_state(&mut timex)
+    }
+
+    /// Escape hatch to call `adjtime` with a hand-built [`libc::timex`], for fields that the
+    /// typed API does not expose. The timex starts out empty with the given `modes`, is passed to
+    /// `f` for further changes, and is returned as filled in by the kernel.
+    ///
+    /// The layout and meaning of [`libc::timex`] differ between operating systems (and between
+    /// libc implementations), so code using this is inherently platform-specific. This method is
+    /// not covered by the stability guarantees of the rest of the crate.
+    pub fn with_raw_timex(
+        &self,
+        modes: libc::c_uint,
+        f: impl FnOnce(&mut libc::timex),
+    ) -> Result<libc::timex, Error> {
+        let mut timex = Self::raw_timex(modes, f);
+        self.adjtime(&mut timex)?;
+
+        Ok(timex)
+    }
+
+    fn raw_timex(modes: libc::c_uint, f: impl FnOnce(&mut libc::timex)) -> libc::timex {
+        let mut timex = libc::timex {
+            modes,
+            ..EMPTY_TIMEX
+        };
+
+        f(&mut timex);
+
+        timex
+    }
+
+    /// Monotonic clocks can only be read, not steered.
+    fn ensure_steerable(&self) -> Result<(), Error> {
+        match self.clock {
+            libc::CLOCK_MONOTONIC => Err(Error::NotSupported),
+            #[cfg(target_os = "linux")]
+            libc::CLOCK_MONOTONIC_RAW | libc::CLOCK_BOOTTIME => Err(Error::NotSupported),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg_attr(target_os = "linux", allow(unused))]
+    fn clock_gettime(&self) -> Result<libc::timespec, Error> {
+        let mut timespec = EMPTY_TIMESPEC;
+
+        // # Safety
+        //
+        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
+        // error https://linux.die.net/man/3/clock_gettime
+        //
+        // The timespec pointer is valid.
+        cerr(unsafe { libc::clock_gettime(self.clock, &mut timespec) })?;
+
+        Ok(timespec)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn clock_settime(&self, mut timespec: libc::timespec) -> Result<(), Error> {
+        self.ensure_steerable()?;
+
+        // clock_settime rejects a tv_nsec outside of [0, 1e9)
+        timespec = normalize_timespec(timespec);
+
+        // # Safety
+        //
+        // using an invalid clock id is safe. `clock_adjtime` will return an EINVAL
+        // error https://linux.die.net/man/3/clock_settime
+        //
+        // The timespec pointer is valid.
+        unsafe { cerr(libc::clock_settime(self.clock, &timespec))? };
+
+        Ok(())
+    }
+
+    /// Read `reference` and this clock `count` times, `interval` apart, and return the pairs of
+    /// their times (reference first), e.g. to estimate the drift of this clock offline. See
+    /// [`sample_drift`](crate::synchronizer::sample_drift).
+    pub fn sample_drift(
+        &self,
+        reference: &UnixClock,
+        count: usize,
+        interval: Duration,
+    ) -> Result<Vec<DriftSample>, Error> {
+        crate::synchronizer::sample_drift(reference, self, count, interval).map_err(|error| {
+            match error {
+                SyncError::Reference(error) | SyncError::Target(error) => error,
+            }
+        })
+    }
+
+    /// A reasonable tolerance for [`UnixClock::step_clock_verified`]. It includes the time it
+    /// takes to read the clock before and after the step.
+    pub const STEP_TOLERANCE: Duration = Duration::from_millis(10);
+
+    /// Step the clock like [`Clock::step_clock`], and check that it actually moved by `offset`,
+    /// by reading the clock before and after the step.
+    ///
+    /// Returns [`Error::StepMismatch`] when the observed step differs from `offset` by more
+    /// than `tolerance` (e.g. [`UnixClock::STEP_TOLERANCE`]), because a clock device silently
+    /// ignored or clamped the step. The tolerance must include the time it takes to read the
+    /// clock, and steps smaller than the tolerance cannot be verified.
+    pub fn step_clock_verified(
+        &self,
+        offset: TimeOffset,
+        tolerance: Duration,
+    ) -> Result<Timestamp, Error> {
+        let before = self.now()?;
+        let applied = self.step_clock(offset)?;
+        let after = self.now()?;
+
+        if step_error(before, after, offset).duration > tolerance {
+            return Err(Error::StepMismatch);
+        }
+
+        Ok(applied)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn step_clock_by_timespec(&self, offset: TimeOffset) -> Result<Timestamp, Error> {
+        self.ensure_may_step()?;
+
+        let timespec = Self::offset_timespec(self.clock_gettime()?, offset);
+
+        self.clock_settime(timespec)?;
+
+        Ok(current_time_timespec(timespec, Precision::Nano))
+    }
+
+    #[cfg(any(not(target_os = "linux"), test))]
+    fn offset_timespec(mut timespec: libc::timespec, offset: TimeOffset) -> libc::timespec {
+        // see https://github.com/rust-lang/libc/issues/1848
+        #[cfg_attr(target_env = "musl", allow(deprecated))]
+        {
+            timespec.tv_sec += offset.seconds as libc::time_t;
+            timespec.tv_nsec += offset.nanos as libc::c_long;
+        }
+
+        normalize_timespec(timespec)
+    }
+
+    fn error_estimate_timex(est_error: Duration, max_error: Duration) -> libc::timex {
+        let modes = libc::MOD_ESTERROR | libc::MOD_MAXERROR;
+
+        libc::timex {
+            modes,
+            esterror: Self::error_micros(est_error),
+            maxerror: Self::error_micros(max_error),
+            ..EMPTY_TIMEX
+        }
+    }
+
+    fn error_micros(error: Duration) -> libc::c_long {
+        // these fields are always in microseconds
+        error.as_nanos() as libc::c_long / 1000
+    }
+
+    /// Set only the maximum error, leaving the estimated error untouched. See
+    /// [`Clock::error_estimate_update`] to set both.
+    pub fn set_maximum_error(&self, max_error: Duration) -> Result<(), Error> {
+        let mut timex = Self::maximum_error_timex(max_error);
+        let result = Error::ignore_not_supported(self.adjtime(&mut timex));
+
+        log_steering!(result, "set_maximum_error({max_error:?})")
+    }
+
+    /// Set only the estimated error, leaving the maximum error untouched. See
+    /// [`Clock::error_estimate_update`] to set both.
+    pub fn set_estimated_error(&self, est_error: Duration) -> Result<(), Error> {
+        let mut timex = Self::estimated_error_timex(est_error);
+        let result = Error::ignore_not_supported(self.adjtime(&mut timex));
+
+        log_steering!(result, "set_estimated_error({est_error:?})")
+    }
+
+    fn maximum_error_timex(max_error: Duration) -> libc::timex {
+        libc::timex {
+            modes: libc::MOD_MAXERROR,
+            maxerror: Self::error_micros(max_error),
+            ..EMPTY_TIMEX
+        }
+    }
+
+    fn estimated_error_timex(est_error: Duration) -> libc::timex {
+        libc::timex {
+            modes: libc::MOD_ESTERROR,
+            esterror: Self::error_micros(est_error),
+            ..EMPTY_TIMEX
+        }
+    }
+
+    fn error_estimate_from_timex(timex: &libc::timex) -> (Duration, Duration) {
+        // these fields are always in microseconds
+        let est_error = Duration::from_micros(timex.esterror.max(0) as u64);
+        let max_error = Duration::from_micros(timex.maxerror.max(0) as u64);
+
+        (est_error, max_error)
+    }
+
+    /// Read the estimated error and maximum error that the kernel currently has for this clock.
+    ///
+    /// These are the values last provided with [`Clock::error_estimate_update`], where the
+    /// kernel grows the maximum error over time. Hardware clocks do not track these values, and
+    /// return [`Error::NotSupported`].
+    pub fn read_error_estimate(&self) -> Result<(Duration, Duration), Error> {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            return Err(Error::NotSupported);
+        }
+
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(Self::error_estimate_from_timex(&timex))
+    }
+
+    /// Read the kernel status flags of this clock.
+    pub fn status(&self) -> Result<ClockStatus, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(ClockStatus::from_bits(timex.status))
+    }
+
+    /// Whether the kernel interprets the offsets and times in a [`libc::timex`] for this clock as
+    /// nanoseconds ([`libc::STA_NANO`]) rather than microseconds.
+    ///
+    /// The offsets this crate submits request nanoseconds with `ADJ_NANO`, so `false` after
+    /// steering means that the clock ignored that request.
+    pub fn is_nanosecond_mode(&self) -> Result<bool, Error> {
+        self.status().map(|status| status.nanosecond())
+    }
+
+    /// Ask the kernel to interpret the offsets and times in a [`libc::timex`] for this clock as
+    /// nanoseconds, and return whether it does so now. FreeBSD starts in microsecond mode, and
+    /// only switches when asked with [`libc::MOD_NANO`].
+    pub fn enable_nanosecond_mode(&self) -> Result<bool, Error> {
+        let mut timex = libc::timex {
+            modes: libc::MOD_NANO,
+            ..EMPTY_TIMEX
+        };
+        self.adjtime(&mut timex)?;
+
+        Ok(ClockStatus::from_bits(timex.status).nanosecond())
+    }
+
+    /// Read the time, frequency, error estimates, TAI offset and status flags of this clock in
+    /// a single kernel call, so that all fields are consistent with each other.
+    ///
+    /// Hardware clocks do not track the error estimates, and report them as `None`.
+    pub fn snapshot(&self) -> Result<ClockSnapshot, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        let time = self.extract_current_time(&timex)?;
+
+        #[cfg(target_os = "linux")]
+        let device = self.fd.is_some();
+        #[cfg(not(target_os = "linux"))]
+        let device = false;
+
+        Ok(ClockSnapshot::from_timex(&timex, time, device))
+    }
+
+    /// Capture the kernel control state of this clock: frequency, status flags, TAI offset and
+    /// error estimates, read in a single kernel call. Pass it to [`UnixClock::restore_state`]
+    /// to continue where a previous process left off, e.g. after restarting a daemon.
+    pub fn capture_state(&self) -> Result<ClockControlState, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(ClockControlState::from_timex(&timex))
+    }
+
+    /// Restore a state obtained with [`UnixClock::capture_state`] in a single kernel call.
+    ///
+    /// The time itself is never changed: no offset is applied, so a stale correction does not
+    /// step or slew the clock. A pending leap second is not re-armed, use
+    /// [`Clock::set_leap_seconds`] for that. Clock devices only restore the frequency.
+    pub fn restore_state(&self, state: &ClockControlState) -> Result<(), Error> {
+        let mut timex = state.restore_timex();
+        self.adjtime(&mut timex)
+    }
+
+    /// Return the clock to an undisciplined baseline in a single kernel call: zero frequency
+    /// offset, no pending offset correction or leap second, unknown error estimates, and the
+    /// kernel phase-locked loop enabled, like after boot.
+    ///
+    /// The time itself is never changed. Clock devices only reset the frequency.
+    pub fn reset_discipline(&self) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            // devices interpret an offset as a phase adjustment, so don't pass one
+            let mut timex = Self::set_frequency_timex(0.0);
+            return log_steering!(self.adjtime(&mut timex), "reset_discipline()");
+        }
+
+        let mut timex = Self::reset_discipline_timex();
+        log_steering!(self.adjtime(&mut timex), "reset_discipline()")
+    }
+
+    fn reset_discipline_timex() -> libc::timex {
+        // the kernel applies the offset after the status, so with the phase-locked loop enabled
+        // a zero MOD_OFFSET only clears the pending correction. Unlike ADJ_SETOFFSET, it never
+        // moves the clock.
+        libc::timex {
+            modes: libc::MOD_FREQUENCY
+                | libc::MOD_OFFSET
+                | libc::MOD_STATUS
+                | libc::MOD_ESTERROR
+                | libc::MOD_MAXERROR,
+            freq: 0,
+            offset: 0,
+            // clears the leap second flags and the other control loops
+            status: libc::STA_PLL | libc::STA_UNSYNC,
+            esterror: UNKNOWN_ERROR_MICROS,
+            maxerror: UNKNOWN_ERROR_MICROS,
+            ..EMPTY_TIMEX
+        }
+    }
+
+    /// Read the precision that the kernel reports for this clock (the `precision` field of
+    /// [`libc::timex`]).
+    ///
+    /// This is an estimate of how accurately the clock can be read, which is what NTP uses in its
+    /// dispersion calculations. It differs from [`Clock::resolution`], which is the smallest
+    /// increment of the clock. Returns [`Error::OperationUnsupported`] when the kernel reports no
+    /// precision, which is the case for hardware clocks.
+    pub fn precision(&self) -> Result<Duration, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Self::precision_from_timex(&timex).ok_or(Error::OperationUnsupported)
+    }
+
+    fn precision_from_timex(timex: &libc::timex) -> Option<Duration> {
+        // this field is always in microseconds
+        match timex.precision {
+            precision if precision > 0 => Some(Duration::from_micros(precision as u64)),
+            _ => None,
+        }
+    }
+
+    /// Read the state of the kernel pulse-per-second discipline of this clock.
+    ///
+    /// Returns [`Error::NotSupported`] for clock devices, which do not track a PPS signal.
+    pub fn pps_status(&self) -> Result<PpsStatus, Error> {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            return Err(Error::NotSupported);
+        }
+
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(PpsStatus::from_timex(&timex))
+    }
+
+    /// The PPS jitter measured by the kernel, see [`PpsStatus::jitter`].
+    ///
+    /// Returns [`Error::NotSupported`] for clock devices.
+    pub fn jitter(&self) -> Result<Duration, Error> {
+        self.pps_status().map(|status| status.jitter)
+    }
+
+    /// The PPS frequency stability measured by the kernel in ppm, see [`PpsStatus::stability`].
+    ///
+    /// Returns [`Error::NotSupported`] for clock devices.
+    pub fn stability_ppm(&self) -> Result<f64, Error> {
+        self.pps_status().map(|status| status.stability)
+    }
+
+    fn jitter_from_timex(timex: &libc::timex) -> Duration {
+        // the jitter is in nanoseconds only when the kernel runs in nanosecond mode
+        let jitter: u64 = timex.jitter.max(0) as _;
+
+        match Precision::from_status(timex.status) {
+            Precision::Nano => Duration::from_nanos(jitter),
+            Precision::Micro => Duration::from_micros(jitter),
+        }
+    }
+
+    fn stability_from_timex(timex: &libc::timex) -> f64 {
+        // always in units of 2^-16 ppm, regardless of STA_NANO
+        timex.stabil as f64 / 65536.0
+    }
+
+    /// Enable or disable the kernel pulse-per-second time ([`libc::STA_PPSTIME`]) and frequency
+    /// ([`libc::STA_PPSFREQ`]) discipline, leaving the other status flags untouched.
+    ///
+    /// Returns [`Error::NotSupported`] for clock devices, which have no kernel PPS discipline.
+    pub fn set_pps_discipline(&self, time: bool, frequency: bool) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            return Err(Error::NotSupported);
+        }
+
+        self.update_status(|status| Self::pps_discipline_status(status, time, frequency))
+            .map(|_| ())
+    }
+
+    /// Read back the leap second indicator programmed with [`Clock::set_leap_seconds`].
+    ///
+    /// A scheduled leap second ([`libc::STA_INS`] or [`libc::STA_DEL`]) takes precedence over
+    /// [`libc::STA_UNSYNC`]. Returns [`Error::Invalid`] when both a leap second insertion and
+    /// deletion are scheduled.
+    pub fn get_leap_indicator(&self) -> Result<LeapIndicator, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        LeapIndicator::from_status_bits(timex.status)
+    }
+
+    /// The leap second that the kernel is going to apply (or is applying right now), if any.
+    ///
+    /// This combines the leap second indicator with the [`ClockState`], so that a leap second
+    /// that has already been applied is not reported while its indicator is still set. The time
+    /// at which it applies is only known for [`UnixClock::CLOCK_REALTIME`], which is in UTC.
+    pub fn scheduled_leap(&self) -> Result<Option<ScheduledLeap>, Error> {
+        let mut timex = EMPTY_TIMEX;
+        let state = self.adjtime_with_state(&mut timex)?;
+
+        let (indicator, in_progress) = match ScheduledLeap::decode(timex.status, state)? {
+            Some(leap) => leap,
+            None => return Ok(None),
+        };
+
+        let at = if self.clock == libc::CLOCK_REALTIME {
+            Some(ScheduledLeap::end_of_day(
+                self.extract_current_time(&timex)?,
+            ))
+        } else {
+            None
+        };
+
+        Ok(Some(ScheduledLeap {
+            indicator,
+            in_progress,
+            at,
+        }))
+    }
+
+    /// Spread a leap second in `direction` over `window`, centered on the next midnight UTC
+    /// (according to this clock), by changing the frequency of this clock instead of stepping it.
+    ///
+    /// Call [`LeapSmear::update`] regularly, at least around the start and the end of the
+    /// window. Do not also schedule the leap second with [`Clock::set_leap_seconds`], or it is
+    /// applied twice.
+    ///
+    /// The frequency offset is one second divided by the window. Returns [`Error::Invalid`] for
+    /// an empty window, and for windows that need a larger offset than the clock accepts, e.g.
+    /// windows shorter than 2000 seconds for the 500 ppm of the kernel.
+    pub fn smear_leap(
+        &self,
+        direction: LeapIndicator,
+        window: Duration,
+    ) -> Result<LeapSmear<'_>, Error> {
+        let smear = LeapSmear {
+            clock: self,
+            direction,
+            window,
+            at: ScheduledLeap::end_of_day(self.now()?),
+            base_frequency: 0.0,
+        };
+
+        if window.is_zero() || smear.rate().abs() > self.frequency_limit_ppm()? {
+            return Err(Error::Invalid);
+        }
+
+        Ok(smear)
+    }
+
+    /// The largest frequency (in ppm) that this clock accepts, see
+    /// [`UnixClock::frequency_limit`]
+    fn frequency_limit_ppm(&self) -> Result<f64, Error> {
+        Ok(self.frequency_limit()? as f64 / 65536.0)
+    }
+
+    /// Mark the clock as synchronized or unsynchronized ([`libc::STA_UNSYNC`]), leaving the
+    /// other status flags, such as the leap second indicators, untouched.
+    pub fn set_synchronized(&self, synced: bool) -> Result<(), Error> {
+        self.update_status(|status| Self::synchronized_status(status, synced))
+            .map(|_| ())
+    }
+
+    fn synchronized_status(status: libc::c_int, synced: bool) -> libc::c_int {
+        if synced {
+            status & !libc::STA_UNSYNC
+        } else {
+            status | libc::STA_UNSYNC
+        }
+    }
+
+    fn pps_discipline_status(status: libc::c_int, time: bool, frequency: bool) -> libc::c_int {
+        let mut status = status & !(libc::STA_PPSTIME | libc::STA_PPSFREQ);
+
+        if time {
+            status |= libc::STA_PPSTIME;
+        }
+
+        if frequency {
+            status |= libc::STA_PPSFREQ;
+        }
+
+        status
+    }
+
+    #[cfg(target_os = "linux")]
+    fn step_clock_timex(offset: TimeOffset) -> libc::timex {
+        // we provide the offset in nanoseconds
+        let modes = libc::ADJ_SETOFFSET | libc::ADJ_NANO;
+
+        let time = libc::timeval {
+            tv_sec: offset.seconds,
+            tv_usec: offset.nanos as libc::suseconds_t,
+        };
+
+        libc::timex {
+            modes,
+            time,
+            ..EMPTY_TIMEX
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn step_clock_by_timex(&self, offset: TimeOffset) -> Result<Timestamp, Error> {
+        self.ensure_may_step()?;
+
+        let mut timex = Self::step_clock_timex(offset);
+        self.adjtime(&mut timex)?;
+        self.extract_current_time(&timex)
+    }
+
+    /// Step the clock like [`Clock::step_clock`], but refuse with [`Error::Invalid`] when the
+    /// offset is larger than `max_step` in either direction. The clock is not touched in that
+    /// case.
+    pub fn step_clock_guarded(
+        &self,
+        offset: TimeOffset,
+        max_step: Duration,
+    ) -> Result<Timestamp, Error> {
+        if Self::offset_magnitude(offset) > max_step {
+            return Err(Error::Invalid);
+        }
+
+        self.step_clock(offset)
+    }
+
+    fn offset_magnitude(offset: TimeOffset) -> Duration {
+        if offset.seconds >= 0 || offset.nanos == 0 {
+            let seconds: u64 = offset.seconds.unsigned_abs() as _;
+            Duration::new(seconds, offset.nanos)
+        } else {
+            // e.g. -0.25s is stored as -1s + 0.75s
+            let seconds: u64 = (offset.seconds + 1).unsigned_abs() as _;
+            Duration::new(seconds, 1_000_000_000 - offset.nanos)
+        }
+    }
+
+    /// Correct the clock by an offset: offsets smaller than `step_threshold` are slewed with
+    /// [`UnixClock::adjust_offset`], larger ones are stepped with [`Clock::step_clock`].
+    ///
+    /// The kernel slews at most half a second ([`UnixClock::MAX_SLEW`]), so with a larger
+    /// `step_threshold` the offsets in between fail with [`Error::Invalid`].
+    ///
+    /// A clock in [frequency-only mode](UnixClock::with_frequency_only) slews all offsets, and
+    /// returns [`Error::Invalid`] for those beyond [`UnixClock::MAX_SLEW`], whatever the threshold.
+    pub fn correct_offset(
+        &self,
+        offset: TimeOffset,
+        step_threshold: Duration,
+    ) -> Result<Correction, Error> {
+        if !self.frequency_only && Self::should_step(offset, step_threshold) {
+            self.step_clock(offset).map(Correction::Stepped)
+        } else {
+            self.adjust_offset(offset).map(Correction::Slewed)
+        }
+    }
+
+    fn should_step(offset: TimeOffset, step_threshold: Duration) -> bool {
+        Self::offset_magnitude(offset) >= step_threshold
+    }
+
+    /// Step the clock forward to the next integer second, so that its phase is aligned to whole
+    /// seconds. Returns the time at which the change was applied.
+    ///
+    /// The offset is computed from a reading of the clock just before the step, so the alignment
+    /// is only as good as the latency of the two calls. A clock that is already on a second
+    /// boundary is left alone.
+    pub fn step_to_next_second(&self) -> Result<Timestamp, Error> {
+        let now = self.now()?;
+
+        match Self::offset_to_next_second(now) {
+            Some(offset) => self.step_clock(offset),
+            None => Ok(now),
+        }
+    }
+
+    /// Slew the clock to the nearest integer second with [`UnixClock::adjust_offset`], the
+    /// gradual counterpart of [`UnixClock::step_to_next_second`].
+    pub fn align_to_second(&self) -> Result<Timestamp, Error> {
+        let now = self.now()?;
+
+        match Self::offset_to_nearest_second(now) {
+            Some(offset) => self.adjust_offset(offset),
+            None => Ok(now),
+        }
+    }
+
+    fn offset_to_next_second(now: Timestamp) -> Option<TimeOffset> {
+        match now.nanos {
+            0 => None,
+            nanos => Some(TimeOffset {
+                seconds: 0,
+                nanos: 1_000_000_000 - nanos,
+            }),
+        }
+    }
+
+    fn offset_to_nearest_second(now: Timestamp) -> Option<TimeOffset> {
+        match now.nanos {
+            0 => None,
+            // moving back by less than half a second, stored as -1s plus the remainder
+            nanos if nanos < 500_000_000 => Some(TimeOffset {
+                seconds: -1,
+                nanos: 1_000_000_000 - nanos,
+            }),
+            _ => Self::offset_to_next_second(now),
+        }
+    }
+
+    /// Slew the clock by an offset, letting the kernel phase-locked loop absorb it gradually.
+    /// Returns the time at which the change was applied.
+    ///
+    /// Unlike [`Clock::step_clock`], which makes the clock jump, this avoids discontinuities in
+    /// time. The kernel only accepts offsets of up to [`UnixClock::MAX_SLEW`], and would
+    /// silently clamp larger ones, so those fail with [`Error::Invalid`] instead.
+    /// The offset is only applied when the kernel phase-locked loop ([`libc::STA_PLL`]) is
+    /// enabled, which [`Clock::disable_kernel_ntp_algorithm`] turns off.
+    pub fn adjust_offset(&self, offset: TimeOffset) -> Result<Timestamp, Error> {
+        let mut timex = Self::adjust_offset_timex(offset)?;
+        self.adjtime(&mut timex)?;
+        self.extract_current_time(&timex)
+    }
+
+    /// The largest offset that [`UnixClock::adjust_offset`] accepts: MAXPHASE of the kernel
+    pub const MAX_SLEW: Duration = Duration::from_millis(500);
+
+    fn adjust_offset_timex(offset: TimeOffset) -> Result<libc::timex, Error> {
+        if Self::offset_magnitude(offset) > Self::MAX_SLEW {
+            return Err(Error::Invalid);
+        }
+
+        // we provide the offset in nanoseconds
+        let modes = libc::MOD_OFFSET | libc::MOD_NANO;
+
+        // within MAXPHASE, so this fits in a c_long
+        let seconds: i64 = offset.seconds as _;
+        let nanos = seconds * 1_000_000_000 + offset.nanos as i64;
+
+        Ok(libc::timex {
+            modes,
+            offset: nanos as libc::c_long,
+            ..EMPTY_TIMEX
+        })
+    }
+
+    /// Change the frequency of the clock and slew it by an offset in a single operation.
+    /// Returns the time at which both changes were applied.
+    ///
+    /// This combines [`Clock::set_frequency`] and [`UnixClock::adjust_offset`], without
+    /// ambiguity about which change happened first. Like [`UnixClock::adjust_offset`], offsets
+    /// beyond [`UnixClock::MAX_SLEW`] fail with [`Error::Invalid`], and like
+    /// [`Clock::set_frequency`], the frequency is clamped to the limit of the clock.
+    ///
+    /// Clock devices return [`Error::OperationUnsupported`]: the kernel applies only one of the
+    /// changes to them, and silently drops the offset. Call [`Clock::set_frequency`] and
+    /// [`UnixClock::adjust_offset`] separately instead.
+    pub fn adjust(&self, frequency: f64, offset: TimeOffset) -> Result<Timestamp, Error> {
+        #[cfg(target_os = "linux")]
+        if self.fd.is_some() {
+            return Err(Error::OperationUnsupported);
+        }
+
+        let mut timex = Self::adjust_timex(frequency, offset, self.frequency_limit()?)?;
+        self.adjtime(&mut timex)?;
+        self.extract_current_time(&timex)
+    }
+
+    /// The limit is in units of 2^-16 ppm, see [`UnixClock::frequency_limit`]
+    fn adjust_timex(frequency: f64, offset: TimeOffset, limit: i64) -> Result<libc::timex, Error> {
+        let frequency = Self::set_frequency_timex_within(frequency, limit);
+        let offset = Self::adjust_offset_timex(offset)?;
+
+        Ok(libc::timex {
+            modes: frequency.modes | offset.modes,
+            freq: frequency.freq,
+            offset: offset.offset,
+            ..EMPTY_TIMEX
+        })
+    }
+
+    /// Set the number of microseconds that the clock advances every kernel tick.
+    ///
+    /// This gives a coarse frequency adjustment on top of [`Clock::set_frequency`], for clocks
+    /// with more drift than the frequency adjustment can compensate. The kernel only accepts
+    /// values within 10% of the nominal tick (`1_000_000 / USER_HZ`), other values return
+    /// [`Error::Invalid`].
+    #[cfg(target_os = "linux")]
+    pub fn set_tick(&self, usec_per_tick: i64) -> Result<(), Error> {
+        // # Safety
+        //
+        // sysconf has no preconditions
+        let user_hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as i64;
+
+        if !Self::tick_in_range(usec_per_tick, user_hz) {
+            return Err(Error::Invalid);
+        }
+
+        let mut timex = Self::set_tick_timex(usec_per_tick);
+        self.adjtime(&mut timex)
+    }
+
+    /// Get the number of microseconds that the clock advances every kernel tick.
+    #[cfg(target_os = "linux")]
+    pub fn get_tick(&self) -> Result<i64, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(timex.tick as _)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn tick_in_range(usec_per_tick: i64, user_hz: i64) -> bool {
+        user_hz > 0 && (900_000 / user_hz..=1_100_000 / user_hz).contains(&usec_per_tick)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_tick_timex(usec_per_tick: i64) -> libc::timex {
+        libc::timex {
+            modes: libc::ADJ_TICK,
+            tick: usec_per_tick as libc::c_long,
+            ..EMPTY_TIMEX
+        }
+    }
+
+    fn extract_current_time(&self, _timex: &libc::timex) -> Result<Timestamp, Error> {
+        #[cfg(target_os = "linux")]
+        // hardware clocks may not report the timestamp
+        if _timex.time.tv_sec != 0 && _timex.time.tv_usec != 0 {
+            // in a timex, the status flag determines precision
+            let precision = Precision::from_status(_timex.status);
+
+            return Ok(current_time_timeval(_timex.time, precision));
+        }
+
+        // clock_gettime always gives nanoseconds
+        let timespec = self.clock_gettime()?;
+        Ok(current_time_timespec(timespec, Precision::Nano))
+    }
+
+    /// Read, modify and write back the timex, returning the time at which the write was applied
+    #[inline(always)]
+    fn update_timex<F>(&self, f: F) -> Result<Timestamp, Error>
+    where
+        F: FnOnce(libc::timex) -> libc::timex,
+    {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        timex = f(timex);
+
+        self.adjtime(&mut timex)?;
+        self.extract_current_time(&timex)
+    }
+
+    #[inline(always)]
+    fn update_status<F>(&self, f: F) -> Result<Timestamp, Error>
+    where
+        F: FnOnce(libc::c_int) -> libc::c_int,
+    {
+        self.update_timex(|mut timex| {
+            // We are setting the status bits
+            timex.modes = libc::MOD_STATUS;
+
+            // update the status flags
+            timex.status = f(timex.status);
+
+            timex
+        })
+    }
+
+    #[cfg(any(target_os = "linux", test))]
+    fn set_frequency_timex(ppm: f64) -> libc::timex {
+        Self::set_frequency_timex_within(ppm, NTP_KAPI_MAX_FREQUENCY)
+    }
+
+    /// The limit is in units of 2^-16 ppm, see [`UnixClock::frequency_limit`]
+    fn set_frequency_timex_within(ppm: f64, limit: i64) -> libc::timex {
+        // NTP Kapi expects frequency adjustment in units of 2^-16 ppm
+        // but our input is in units of seconds drift per second, so convert.
+        let frequency = (ppm * 65536.0).round() as i64;
+
+        Self::set_scaled_frequency_timex_within(frequency, limit)
+    }
+
+    /// The frequency and the limit are in units of 2^-16 ppm
+    fn set_scaled_frequency_timex_within(frequency: i64, limit: i64) -> libc::timex {
+        // We do an offset with precision
+        let mut timex = EMPTY_TIMEX;
+
+        // set the frequency (MOD_FREQUENCY is an alias for ADJ_FREQUENCY on linux)
+        timex.modes = libc::MOD_FREQUENCY;
+        timex.freq = frequency.clamp(-limit, limit) as libc::c_long;
+
+        timex
+    }
+
+    /// Change the frequency of the clock, reporting whether the frequency had to be clamped to
+    /// the range that the kernel accepts.
+    ///
+    /// A control loop can use this to detect that frequency adjustment is saturated, and fall
+    /// back to stepping the clock.
+    pub fn try_set_frequency(&self, frequency: f64) -> Result<FrequencyChange, Error> {
+        let mut timex = Self::set_frequency_timex_within(frequency, self.frequency_limit()?);
+        let (applied, clamped) = Self::applied_frequency(frequency, &timex);
+
+        self.adjtime(&mut timex)?;
+
+        Ok(FrequencyChange {
+            applied_at: self.extract_current_time(&timex)?,
+            frequency: applied,
+            clamped,
+        })
+    }
+
+    /// Change the frequency of the clock like [`Clock::set_frequency`], and return the frequency
+    /// (in ppm) that it had before, together with the time at which the change was applied.
+    ///
+    /// The kernel reports the new frequency after a change, so the old one is read first. Another
+    /// process that changes the frequency between these two calls goes unnoticed.
+    pub fn swap_frequency(&self, frequency: f64) -> Result<(f64, Timestamp), Error> {
+        let result = self.frequency_limit().and_then(|limit| {
+            let mut current = EMPTY_TIMEX;
+            self.adjtime(&mut current)?;
+
+            let mut timex = Self::set_frequency_timex_within(frequency, limit);
+            self.adjtime(&mut timex)?;
+
+            Ok((
+                Self::frequency_from_timex(&current),
+                self.extract_current_time(&timex)?,
+            ))
+        });
+
+        log_steering!(result, "swap_frequency({frequency})")
+    }
+
+    /// The frequency of a [`libc::timex`] in ppm
+    fn frequency_from_timex(timex: &libc::timex) -> f64 {
+        (timex.freq as f64) / 65536.0
+    }
+
+    /// Change the frequency of the clock, unless it is already within `tolerance` (in ppm) of
+    /// `frequency`. Returns the time at which the change was applied, or `None` when the clock
+    /// was left alone.
+    ///
+    /// The comparison uses the frequency as the kernel would store it, after rounding and
+    /// clamping, so a `tolerance` of zero skips writing the frequency that is already set.
+    pub fn ensure_frequency(
+        &self,
+        frequency: f64,
+        tolerance: f64,
+    ) -> Result<Option<Timestamp>, Error> {
+        let mut current = EMPTY_TIMEX;
+        self.adjtime(&mut current)?;
+
+        let mut timex = Self::set_frequency_timex_within(frequency, self.frequency_limit()?);
+        if Self::frequency_within_tolerance(&current, &timex, tolerance) {
+            return Ok(None);
+        }
+
+        let result = self
+            .adjtime(&mut timex)
+            .and_then(|()| self.extract_current_time(&timex));
+
+        log_steering!(result, "ensure_frequency({frequency}, {tolerance})").map(Some)
+    }
+
+    fn frequency_within_tolerance(
+        current: &libc::timex,
+        requested: &libc::timex,
+        tolerance: f64,
+    ) -> bool {
+        let difference = (requested.freq as f64 - current.freq as f64) / 65536.0;
+        difference.abs() <= tolerance
+    }
+
+    /// Absorb an offset by temporarily changing the frequency of the clock, such that after
+    /// `duration` the clock has moved by `offset` relative to its current frequency. This is the
+    /// classic `adjtime()` behavior, without relying on the kernel to end the slew.
+    ///
+    /// The kernel keeps the changed frequency until it is changed again: poll
+    /// [`OffsetSlew::is_finished`] and restore [`OffsetSlew::base_frequency`] with
+    /// [`Clock::set_frequency`]. While the kernel phase-locked loop ([`libc::STA_PLL`]) is
+    /// enabled it keeps adjusting the frequency as well, so disable it first with
+    /// [`Clock::disable_kernel_ntp_algorithm`].
+    ///
+    /// Returns [`Error::Invalid`] when `duration` is zero, or when the required frequency is
+    /// outside the range that the kernel accepts. The frequency is left unchanged in that case.
+    pub fn slew_offset_over(
+        &self,
+        offset: TimeOffset,
+        duration: Duration,
+    ) -> Result<OffsetSlew, Error> {
+        if duration.is_zero() {
+            return Err(Error::Invalid);
+        }
+
+        let base_frequency = self.get_frequency()?;
+        let frequency = base_frequency + Self::slew_frequency(offset, duration);
+
+        let change = self.try_set_frequency(frequency)?;
+        if change.clamped {
+            self.set_frequency(base_frequency)?;
+            return Err(Error::Invalid);
+        }
+
+        Ok(OffsetSlew {
+            applied_at: change.applied_at,
+            until: Self::slew_end(change.applied_at, offset, duration).ok_or(Error::Invalid)?,
+            base_frequency,
+        })
+    }
+
+    /// The frequency change (in ppm) that moves the clock by `offset` over `duration`
+    fn slew_frequency(offset: TimeOffset, duration: Duration) -> f64 {
+        let offset = offset.seconds as f64 + offset.nanos as f64 / 1e9;
+
+        offset / duration.as_secs_f64() * 1e6
+    }
+
+    /// The time on the slewed clock at which the slew is complete
+    fn slew_end(start: Timestamp, offset: TimeOffset, duration: Duration) -> Option<Timestamp> {
+        let end = start.checked_add(duration)?;
+
+        Timestamp {
+            seconds: end.seconds.checked_add(offset.seconds)?,
+            nanos: end.nanos,
+        }
+        .checked_add(Duration::from_nanos(offset.nanos as u64))
+    }
+
+    /// Prepare to move the frequency of the clock gradually to `target` (in ppm), at `rate` ppm
+    /// per second. Changing the frequency gradually avoids a sudden change in the rate of the
+    /// clock.
+    ///
+    /// Nothing changes until [`FrequencyRamp::step`] is called. Returns [`Error::Invalid`] when
+    /// `rate` is not a positive number.
+    pub fn ramp_frequency(&self, target: f64, rate: f64) -> Result<FrequencyRamp<'_>, Error> {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(Error::Invalid);
+        }
+
+        Ok(FrequencyRamp {
+            clock: self,
+            current: self.get_frequency()?,
+            target,
+            rate,
+        })
+    }
+
+    fn applied_frequency(requested: f64, timex: &libc::timex) -> (f64, bool) {
+        let requested = (requested * 65536.0).round() as i64;
+        let applied: i64 = timex.freq as _;
+
+        (applied as f64 / 65536.0, requested != applied)
+    }
+
+    /// Get the frequency of the clock in parts per billion.
+    ///
+    /// Unlike [`Clock::get_frequency`], this uses integer arithmetic, rounding to the nearest
+    /// integer.
+    pub fn get_frequency_ppb(&self) -> Result<i64, Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(Self::ppb_from_scaled_ppm(timex.freq as _))
+    }
+
+    /// Change the frequency of the clock, in parts per billion.
+    /// Returns the time at which the change was applied.
+    ///
+    /// Unlike [`Clock::set_frequency`], this uses integer arithmetic, rounding to the nearest
+    /// unit of 2^-16 ppm that the kernel accepts.
+    pub fn set_frequency_ppb(&self, ppb: i64) -> Result<Timestamp, Error> {
+        let mut timex = Self::set_scaled_frequency_timex_within(
+            Self::scaled_ppm_from_ppb(ppb),
+            self.frequency_limit()?,
+        );
+        self.adjtime(&mut timex)?;
+        self.extract_current_time(&timex)
+    }
+
+    /// Convert from units of 2^-16 ppm to ppb, rounding half away from zero.
+    fn ppb_from_scaled_ppm(frequency: i64) -> i64 {
+        // in i128, so that neither the scaling nor the rounding can overflow
+        let scaled = i128::from(frequency) * 1000;
+        saturating_i64((scaled + scaled.signum() * (1 << 15)) / (1 << 16))
+    }
+
+    /// Convert from ppb to units of 2^-16 ppm, rounding half away from zero. Values beyond the
+    /// range of an i64 saturate, and are clamped to the frequency limit later anyway.
+    fn scaled_ppm_from_ppb(ppb: i64) -> i64 {
+        // in i128, so that neither the scaling nor the rounding can overflow
+        let scaled = i128::from(ppb) * (1 << 16);
+        saturating_i64((scaled + scaled.signum() * 500) / 1000)
+    }
+}
+
+impl Clock for UnixClock {
+    type Error = Error;
+
+    fn now(&self) -> Result<Timestamp, Self::Error> {
+        let mut ntp_kapi_timex = EMPTY_TIMEX;
+
+        if self.adjtime(&mut ntp_kapi_timex).is_ok() {
+            self.extract_current_time(&ntp_kapi_timex)
+        } else {
+            self.clock_gettime()
+                .map(|ts| current_time_timespec(ts, Precision::Nano))
+        }
+    }
+
+    /// The resolution is read once, and then cached. Use [`UnixClock::resolution_uncached`]
+    /// to read it again.
+    fn resolution(&self) -> Result<Timestamp, Self::Error> {
+        self.cached_resolution(|| self.resolution_uncached())
+    }
+
+    fn get_frequency(&self) -> Result<f64, Self::Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        Ok(Self::frequency_from_timex(&timex))
+    }
+
+    fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
+        let result = self.frequency_limit().and_then(|limit| {
+            let mut timex = Self::set_frequency_timex_within(frequency, limit);
+            self.adjtime(&mut timex)?;
+            self.extract_current_time(&timex)
+        });
+
+        log_steering!(result, "set_frequency({frequency})")
+    }
+
+    #[cfg(target_os = "linux")]
+    fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+        log_steering!(self.step_clock_by_timex(offset), "step_clock({offset:?})")
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
     fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
-        self.step_clock_by_timespec(offset)
+        log_steering!(
+            self.step_clock_by_timespec(offset),
+            "step_clock({offset:?})"
+        )
+    }
+
+    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+        let result = self.update_status(|status| {
+            (status & !(libc::STA_UNSYNC | libc::STA_INS | libc::STA_DEL))
+                | leap_status.as_status_bit()
+        });
+
+        log_steering!(result, "set_leap_seconds({leap_status:?})")
+    }
+
+    fn error_estimate_update(
+        &self,
+        est_error: Duration,
+        max_error: Duration,
+    ) -> Result<Timestamp, Self::Error> {
+        let mut timex = Self::error_estimate_timex(est_error, max_error);
+        let result = match self.adjtime(&mut timex) {
+            Ok(()) => self.extract_current_time(&timex),
+            // hardware clocks do not track error estimates, which is fine
+            Err(Error::NotSupported | Error::OperationUnsupported) => self.now(),
+            Err(error) => Err(error),
+        };
+
+        log_steering!(
+            result,
+            "error_estimate_update({est_error:?}, {max_error:?})"
+        )
+    }
+
+    fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+        let mut timex = EMPTY_TIMEX;
+        self.adjtime(&mut timex)?;
+
+        // We are setting the status bits
+        timex.modes = libc::MOD_STATUS;
+
+        // Disable all kernel time control loops (phase lock, frequency lock, pps time and pps frequency).
+        timex.status &= !(libc::STA_PLL | libc::STA_FLL | libc::STA_PPSTIME | libc::STA_PPSFREQ);
+
+        // ignore if we cannot disable the kernel time control loops (e.g. external clocks)
+        Error::ignore_not_supported(self.adjtime(&mut timex))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_tai(&self, tai_offset: i32) -> Result<(), Error> {
+        let mut timex = libc::timex {
+            modes: libc::ADJ_TAI,
+            constant: tai_offset as _,
+            ..EMPTY_TIMEX
+        };
+
+        // like `tai_info`, the monotonic clocks have no TAI offset
+        let result = self
+            .ensure_steerable()
+            .and_then(|()| self.clock_adjtime(&mut timex).map(|_| ()));
+
+        log_steering!(result, "set_tai({tai_offset})")
+    }
+
+    #[cfg(target_os = "freebsd")]
+    fn set_tai(&self, tai_offset: i32) -> Result<(), Error> {
+        // the kernel silently ignores offsets that are not positive
+        let result = if tai_offset > 0 {
+            let mut timex = libc::timex {
+                modes: libc::MOD_TAI,
+                constant: tai_offset as _,
+                ..EMPTY_TIMEX
+            };
+
+            self.adjtime(&mut timex)
+        } else {
+            Err(Error::Invalid)
+        };
+
+        log_steering!(result, "set_tai({tai_offset})")
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+    fn set_tai(&self, tai_offset: i32) -> Result<(), Error> {
+        log_steering!(Err(Error::NotSupported), "set_tai({tai_offset})")
+    }
+
+    #[cfg(target_os = "linux")]
+    fn get_tai(&self) -> Result<i32, Error> {
+        // like `tai_info`, the monotonic clocks have no TAI offset
+        self.ensure_steerable()?;
+
+        let mut timex = EMPTY_TIMEX;
+        if self.clock_adjtime(&mut timex).is_ok() {
+            Ok(timex.tai)
+        } else {
+            // hardware clock which doesn't have an offset anyway
+            Ok(0)
+        }
+    }
+
+    #[cfg(target_os = "freebsd")]
+    fn get_tai(&self) -> Result<i32, Error> {
+        // only the kernel clock keeps a TAI offset
+        if self.clock != libc::CLOCK_REALTIME {
+            return Err(Error::NotSupported);
+        }
+
+        // # Safety
+        //
+        // ntptimeval only contains integers, for which all zeroes is a valid value
+        let mut ntv: libc::ntptimeval = unsafe { std::mem::zeroed() };
+
+        // # Safety
+        //
+        // ntp_gettime receives a valid ntptimeval mutable pointer
+        cerr(unsafe { libc::ntp_gettime(&mut ntv) })?;
+
+        Ok(ntv.tai as _)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+    fn get_tai(&self) -> Result<i32, Error> {
+        Err(Error::NotSupported)
+    }
+}
+
+#[cfg(target_os = "linux")]
+const PTP_ENABLE_FEATURE: u32 = 1 << 0;
+#[cfg(target_os = "linux")]
+const PTP_RISING_EDGE: u32 = 1 << 1;
+#[cfg(target_os = "linux")]
+const PTP_FALLING_EDGE: u32 = 1 << 2;
+#[cfg(target_os = "linux")]
+const PTP_STRICT_FLAGS: u32 = 1 << 3;
+
+/// Which edges of an external signal to timestamp, see
+/// [`UnixClock::enable_external_timestamp`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ExtTsFlags {
+    /// Stop timestamping events on the channel
+    #[default]
+    Disabled,
+    /// Timestamp the rising edge of the signal
+    RisingEdge,
+    /// Timestamp the falling edge of the signal
+    FallingEdge,
+    /// Timestamp both edges of the signal
+    BothEdges,
+}
+
+#[cfg(target_os = "linux")]
+impl ExtTsFlags {
+    fn as_raw(self) -> u32 {
+        // With strict flags, the driver rejects edges that it cannot timestamp, instead of
+        // silently timestamping a different edge.
+        let enable = PTP_ENABLE_FEATURE | PTP_STRICT_FLAGS;
+
+        match self {
+            ExtTsFlags::Disabled => 0,
+            ExtTsFlags::RisingEdge => enable | PTP_RISING_EDGE,
+            ExtTsFlags::FallingEdge => enable | PTP_FALLING_EDGE,
+            ExtTsFlags::BothEdges => enable | PTP_RISING_EDGE | PTP_FALLING_EDGE,
+        }
+    }
+}
+
+/// An external timestamp event, see [`UnixClock::read_external_timestamp`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PtpExtTsEvent {
+    /// The channel on which the event occurred
+    pub channel: u32,
+    /// The time of the event, according to the clock device
+    pub timestamp: Timestamp,
+}
+
+#[cfg(target_os = "linux")]
+impl PtpExtTsEvent {
+    fn from_raw(event: &PtpExtTsEventRaw) -> Self {
+        Self {
+            channel: event.index,
+            timestamp: timestamp_from_ptp_clock_time(&event.t),
+        }
+    }
+}
+
+// libc's `ptp_extts_event` does not expose its `index` field
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct PtpExtTsEventRaw {
+    t: libc::ptp_clock_time,
+    index: libc::c_uint,
+    flags: libc::c_uint,
+    rsv: [libc::c_uint; 2],
+}
+
+#[cfg(target_os = "linux")]
+impl Default for PtpExtTsEventRaw {
+    fn default() -> Self {
+        Self {
+            t: ptp_clock_time(0, 0),
+            index: 0,
+            flags: 0,
+            rsv: [0; 2],
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const PTP_PEROUT_DUTY_CYCLE: u32 = 1 << 1;
+#[cfg(target_os = "linux")]
+const PTP_PEROUT_PHASE: u32 = 1 << 2;
+
+/// Options for a periodic output signal, see [`UnixClock::configure_periodic_output`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PerOutFlags {
+    /// How long the signal is active in each period. When `None`, the driver picks a default.
+    pub duty_cycle: Option<Duration>,
+    /// Interpret the start time as a phase offset into each period, rather than as an absolute
+    /// time.
+    pub phase: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl PerOutFlags {
+    fn as_raw(self) -> u32 {
+        let mut flags = 0;
+
+        if self.duty_cycle.is_some() {
+            flags |= PTP_PEROUT_DUTY_CYCLE;
+        }
+
+        if self.phase {
+            flags |= PTP_PEROUT_PHASE;
+        }
+
+        flags
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn ptp_clock_time(sec: i64, nsec: u32) -> libc::ptp_clock_time {
+    libc::ptp_clock_time {
+        sec,
+        nsec,
+        reserved: 0,
+    }
+}
+
+/// The function of a pin of a clock device, see [`UnixClock::set_pin_function`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PinFunction {
+    /// The pin is not used
+    #[default]
+    None,
+    /// The pin is an input for external timestamps
+    ExternalTimestamp,
+    /// The pin is a periodic output
+    PeriodicOutput,
+    /// The pin is used for physical layer synchronization
+    PhysicalSync,
+}
+
+#[cfg(target_os = "linux")]
+impl PinFunction {
+    fn as_raw(self) -> libc::c_uint {
+        match self {
+            PinFunction::None => libc::PTP_PF_NONE,
+            PinFunction::ExternalTimestamp => libc::PTP_PF_EXTTS,
+            PinFunction::PeriodicOutput => libc::PTP_PF_PEROUT,
+            PinFunction::PhysicalSync => libc::PTP_PF_PHYSYNC,
+        }
+    }
+
+    fn from_raw(raw: libc::c_uint) -> Option<Self> {
+        match raw {
+            libc::PTP_PF_NONE => Some(PinFunction::None),
+            libc::PTP_PF_EXTTS => Some(PinFunction::ExternalTimestamp),
+            libc::PTP_PF_PEROUT => Some(PinFunction::PeriodicOutput),
+            libc::PTP_PF_PHYSYNC => Some(PinFunction::PhysicalSync),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn empty_ptp_pin_desc(index: u32) -> libc::ptp_pin_desc {
+    libc::ptp_pin_desc {
+        name: [0; 64],
+        index,
+        func: 0,
+        chan: 0,
+        rsv: [0; 5],
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn timestamp_from_ptp_clock_time(time: &libc::ptp_clock_time) -> Timestamp {
+    Timestamp {
+        seconds: time.sec as _,
+        nanos: time.nsec,
+    }
+}
+
+/// The state of a clock at a single moment, see [`UnixClock::snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSnapshot {
+    /// The current time of the clock
+    pub time: Timestamp,
+    /// The frequency of the clock, in the unit of [`Clock::set_frequency`]
+    pub frequency: f64,
+    /// The estimated error, see [`Clock::error_estimate_update`]
+    pub estimated_error: Option<Duration>,
+    /// The maximum error, see [`Clock::error_estimate_update`]
+    pub maximum_error: Option<Duration>,
+    /// The offset between TAI and UTC, only available on Linux
+    pub tai: Option<i32>,
+    /// The kernel status flags
+    pub status: ClockStatus,
+}
+
+impl ClockSnapshot {
+    fn from_timex(timex: &libc::timex, time: Timestamp, device: bool) -> Self {
+        let (estimated_error, maximum_error) = if device {
+            (None, None)
+        } else {
+            let (est_error, max_error) = UnixClock::error_estimate_from_timex(timex);
+            (Some(est_error), Some(max_error))
+        };
+
+        #[cfg(target_os = "linux")]
+        let tai = Some(timex.tai);
+        #[cfg(not(target_os = "linux"))]
+        let tai = None;
+
+        Self {
+            time,
+            frequency: UnixClock::frequency_from_timex(timex),
+            estimated_error,
+            maximum_error,
+            tai,
+            status: ClockStatus::from_bits(timex.status),
+        }
+    }
+}
+
+/// The kernel control state of a clock, see [`UnixClock::capture_state`].
+///
+/// The fields are in the units of the kernel, so that a restore is exact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClockControlState {
+    /// The frequency, in units of 2^-16 ppm
+    pub frequency: i64,
+    /// The raw kernel status flags, see [`ClockStatus`]
+    pub status: i32,
+    /// The offset between TAI and UTC, only available on Linux
+    pub tai: Option<i32>,
+    /// The estimated error, in microseconds
+    pub estimated_error: i64,
+    /// The maximum error, in microseconds
+    pub maximum_error: i64,
+}
+
+impl ClockControlState {
+    fn from_timex(timex: &libc::timex) -> Self {
+        #[cfg(target_os = "linux")]
+        let tai = Some(timex.tai);
+        #[cfg(not(target_os = "linux"))]
+        let tai = None;
+
+        Self {
+            frequency: timex.freq as _,
+            status: timex.status as _,
+            tai,
+            estimated_error: timex.esterror as _,
+            maximum_error: timex.maxerror as _,
+        }
+    }
+
+    /// Status flags that are not written back: a captured leap second is likely stale by the
+    /// time the state is restored, and the read-only flags are reported by the kernel.
+    const NOT_RESTORED: i32 = libc::STA_INS | libc::STA_DEL | libc::STA_RONLY;
+
+    fn restore_timex(&self) -> libc::timex {
+        // deliberately no MOD_OFFSET (or ADJ_SETOFFSET), which would re-apply a stale correction
+        let mut timex = libc::timex {
+            modes: libc::MOD_FREQUENCY | libc::MOD_STATUS | libc::MOD_ESTERROR | libc::MOD_MAXERROR,
+            freq: self.frequency as _,
+            status: (self.status & !Self::NOT_RESTORED) as _,
+            esterror: self.estimated_error as _,
+            maxerror: self.maximum_error as _,
+            ..EMPTY_TIMEX
+        };
+
+        if let Some(tai) = self.tai {
+            // the TAI offset is set through the constant field
+            timex.modes |= libc::MOD_TAI;
+            timex.constant = tai as _;
+        }
+
+        timex
+    }
+}
+
+/// The kernel pulse-per-second state, see [`UnixClock::pps_status`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PpsStatus {
+    /// A valid PPS signal is present ([`libc::STA_PPSSIGNAL`])
+    pub signal: bool,
+    /// The PPS signal jitter exceeds the limit ([`libc::STA_PPSJITTER`])
+    pub jitter_exceeded: bool,
+    /// The PPS signal wander exceeds the limit ([`libc::STA_PPSWANDER`])
+    pub wander_exceeded: bool,
+    /// The PPS signal has a calibration error ([`libc::STA_PPSERROR`])
+    pub error: bool,
+    /// The measured PPS jitter
+    pub jitter: Duration,
+    /// The measured PPS frequency stability, in parts per million
+    pub stability: f64,
+    /// The number of times the jitter limit was exceeded
+    pub jitter_count: i64,
+    /// The number of calibration intervals
+    pub calibration_count: i64,
+}
+
+impl PpsStatus {
+    fn from_timex(timex: &libc::timex) -> Self {
+        let status = ClockStatus::from_bits(timex.status);
+
+        Self {
+            signal: status.pps_signal(),
+            jitter_exceeded: status.pps_jitter(),
+            wander_exceeded: status.pps_wander(),
+            error: status.pps_error(),
+            jitter: UnixClock::jitter_from_timex(timex),
+            stability: UnixClock::stability_from_timex(timex),
+            jitter_count: timex.jitcnt as _,
+            calibration_count: timex.calcnt as _,
+        }
+    }
+}
+
+/// The timestamping capabilities of a network interface, see [`timestamping_info`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimestampingInfo {
+    /// The supported `SOF_TIMESTAMPING_*` flags
+    pub flags: TimestampingFlags,
+    /// The index of the clock device (PHC) of the interface, if it has one
+    pub phc_index: Option<i32>,
+    /// The supported modes for hardware timestamping of transmitted packets
+    pub tx_types: Vec<TxType>,
+    /// The supported filters for hardware timestamping of received packets
+    pub rx_filters: Vec<RxFilter>,
+}
+
+#[cfg(target_os = "linux")]
+impl TimestampingInfo {
+    fn from_ethtool(info: &EthtoolTsInfo) -> Self {
+        Self {
+            flags: TimestampingFlags::from_bits(info.so_timestamping),
+            phc_index: (info.phc_index >= 0).then_some(info.phc_index),
+            tx_types: TxType::ALL
+                .into_iter()
+                .filter(|tx_type| info.tx_types & (1 << tx_type.as_raw()) != 0)
+                .collect(),
+            rx_filters: RxFilter::ALL
+                .into_iter()
+                .filter(|filter| info.rx_filters & (1 << filter.as_raw()) != 0)
+                .collect(),
+        }
+    }
+}
+
+/// The `SOF_TIMESTAMPING_*` flags supported by a network interface.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TimestampingFlags {
+    bits: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl TimestampingFlags {
+    /// Interpret a set of `SOF_TIMESTAMPING_*` flags.
+    pub fn from_bits(bits: u32) -> Self {
+        Self { bits }
+    }
+
+    /// The raw flags.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    fn contains(&self, flag: u32) -> bool {
+        self.bits & flag != 0
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_TX_HARDWARE`]: transmitted packets can be timestamped by the
+    /// hardware.
+    pub fn tx_hardware(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_TX_HARDWARE)
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_TX_SOFTWARE`]: transmitted packets can be timestamped by the
+    /// kernel.
+    pub fn tx_software(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_TX_SOFTWARE)
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_RX_HARDWARE`]: received packets can be timestamped by the
+    /// hardware.
+    pub fn rx_hardware(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_RX_HARDWARE)
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_RX_SOFTWARE`]: received packets can be timestamped by the
+    /// kernel.
+    pub fn rx_software(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_RX_SOFTWARE)
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_SOFTWARE`]: software timestamps can be reported.
+    pub fn software(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_SOFTWARE)
+    }
+
+    /// [`libc::SOF_TIMESTAMPING_RAW_HARDWARE`]: hardware timestamps can be reported.
+    pub fn raw_hardware(&self) -> bool {
+        self.contains(libc::SOF_TIMESTAMPING_RAW_HARDWARE)
+    }
+}
+
+/// A mode for hardware timestamping of transmitted packets (`HWTSTAMP_TX_*`)
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxType {
+    /// No outgoing packets are timestamped
+    Off,
+    /// All outgoing packets are timestamped
+    On,
+    /// PTP sync messages are timestamped and updated in place (one-step)
+    OneStepSync,
+    /// Like [`TxType::OneStepSync`], and peer delay responses are updated as well
+    OneStepP2p,
+}
+
+#[cfg(target_os = "linux")]
+impl TxType {
+    const ALL: [Self; 4] = [Self::Off, Self::On, Self::OneStepSync, Self::OneStepP2p];
+
+    fn as_raw(self) -> u32 {
+        match self {
+            Self::Off => libc::HWTSTAMP_TX_OFF,
+            Self::On => libc::HWTSTAMP_TX_ON,
+            Self::OneStepSync => libc::HWTSTAMP_TX_ONESTEP_SYNC,
+            Self::OneStepP2p => libc::HWTSTAMP_TX_ONESTEP_P2P,
+        }
+    }
+}
+
+/// A filter for hardware timestamping of received packets (`HWTSTAMP_FILTER_*`)
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RxFilter {
+    /// No incoming packets are timestamped
+    None,
+    /// All incoming packets are timestamped
+    All,
+    /// Some packets are timestamped, at least those requested
+    Some,
+    /// PTP v1, UDP, any kind of event packet
+    PtpV1L4Event,
+    /// PTP v1, UDP, sync packet
+    PtpV1L4Sync,
+    /// PTP v1, UDP, delay request packet
+    PtpV1L4DelayReq,
+    /// PTP v2, UDP, any kind of event packet
+    PtpV2L4Event,
+    /// PTP v2, UDP, sync packet
+    PtpV2L4Sync,
+    /// PTP v2, UDP, delay request packet
+    PtpV2L4DelayReq,
+    /// 802.AS1, ethernet, any kind of event packet
+    PtpV2L2Event,
+    /// 802.AS1, ethernet, sync packet
+    PtpV2L2Sync,
+    /// 802.AS1, ethernet, delay request packet
+    PtpV2L2DelayReq,
+    /// PTP v2 or 802.AS1, any layer, any kind of event packet
+    PtpV2Event,
+    /// PTP v2 or 802.AS1, any layer, sync packet
+    PtpV2Sync,
+    /// PTP v2 or 802.AS1, any layer, delay request packet
+    PtpV2DelayReq,
+    /// NTP, UDP, all versions and packet modes
+    NtpAll,
+}
+
+#[cfg(target_os = "linux")]
+impl RxFilter {
+    const ALL: [Self; 16] = [
+        Self::None,
+        Self::All,
+        Self::Some,
+        Self::PtpV1L4Event,
+        Self::PtpV1L4Sync,
+        Self::PtpV1L4DelayReq,
+        Self::PtpV2L4Event,
+        Self::PtpV2L4Sync,
+        Self::PtpV2L4DelayReq,
+        Self::PtpV2L2Event,
+        Self::PtpV2L2Sync,
+        Self::PtpV2L2DelayReq,
+        Self::PtpV2Event,
+        Self::PtpV2Sync,
+        Self::PtpV2DelayReq,
+        Self::NtpAll,
+    ];
+
+    fn as_raw(self) -> u32 {
+        match self {
+            Self::None => libc::HWTSTAMP_FILTER_NONE,
+            Self::All => libc::HWTSTAMP_FILTER_ALL,
+            Self::Some => libc::HWTSTAMP_FILTER_SOME,
+            Self::PtpV1L4Event => libc::HWTSTAMP_FILTER_PTP_V1_L4_EVENT,
+            Self::PtpV1L4Sync => libc::HWTSTAMP_FILTER_PTP_V1_L4_SYNC,
+            Self::PtpV1L4DelayReq => libc::HWTSTAMP_FILTER_PTP_V1_L4_DELAY_REQ,
+            Self::PtpV2L4Event => libc::HWTSTAMP_FILTER_PTP_V2_L4_EVENT,
+            Self::PtpV2L4Sync => libc::HWTSTAMP_FILTER_PTP_V2_L4_SYNC,
+            Self::PtpV2L4DelayReq => libc::HWTSTAMP_FILTER_PTP_V2_L4_DELAY_REQ,
+            Self::PtpV2L2Event => libc::HWTSTAMP_FILTER_PTP_V2_L2_EVENT,
+            Self::PtpV2L2Sync => libc::HWTSTAMP_FILTER_PTP_V2_L2_SYNC,
+            Self::PtpV2L2DelayReq => libc::HWTSTAMP_FILTER_PTP_V2_L2_DELAY_REQ,
+            Self::PtpV2Event => libc::HWTSTAMP_FILTER_PTP_V2_EVENT,
+            Self::PtpV2Sync => libc::HWTSTAMP_FILTER_PTP_V2_SYNC,
+            Self::PtpV2DelayReq => libc::HWTSTAMP_FILTER_PTP_V2_DELAY_REQ,
+            Self::NtpAll => libc::HWTSTAMP_FILTER_NTP_ALL,
+        }
+    }
+}
+
+/// How [`UnixClock::correct_offset`] corrected the clock, with the time at which the correction
+/// was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Correction {
+    /// The offset was slewed with [`UnixClock::adjust_offset`]
+    Slewed(Timestamp),
+    /// The clock was stepped with [`Clock::step_clock`]
+    Stepped(Timestamp),
+}
+
+/// A slew in progress, see [`UnixClock::slew_offset_over`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffsetSlew {
+    /// The time at which the slew started
+    pub applied_at: Timestamp,
+    /// The time of the slewed clock at which the slew is complete
+    pub until: Timestamp,
+    /// The frequency to restore once the slew is complete
+    pub base_frequency: f64,
+}
+
+impl OffsetSlew {
+    /// Whether the slew is complete at time `now` of the slewed clock
+    pub fn is_finished(&self, now: Timestamp) -> bool {
+        now >= self.until
+    }
+}
+
+/// A gradual frequency change in progress, see [`UnixClock::ramp_frequency`].
+#[derive(Debug)]
+pub struct FrequencyRamp<'a> {
+    clock: &'a UnixClock,
+    current: f64,
+    target: f64,
+    rate: f64,
+}
+
+impl FrequencyRamp<'_> {
+    /// Advance the frequency by the rate of the ramp over `elapsed`, without overshooting the
+    /// target, and apply it to the clock. Returns the time at which the change was applied.
+    pub fn step(&mut self, elapsed: Duration) -> Result<Timestamp, Error> {
+        let next = Self::next_frequency(self.current, self.target, self.rate, elapsed);
+        let applied_at = self.clock.set_frequency(next)?;
+
+        self.current = next;
+
+        Ok(applied_at)
+    }
+
+    fn next_frequency(current: f64, target: f64, rate: f64, elapsed: Duration) -> f64 {
+        let max_change = rate * elapsed.as_secs_f64();
+        let change = target - current;
+
+        // land on the target exactly, `current + (target - current)` need not round-trip
+        if change.abs() <= max_change {
+            target
+        } else {
+            current + change.clamp(-max_change, max_change)
+        }
+    }
+
+    /// The frequency that was last applied
+    pub fn frequency(&self) -> f64 {
+        self.current
+    }
+
+    /// Whether the target frequency has been reached
+    pub fn is_complete(&self) -> bool {
+        self.current == self.target
+    }
+}
+
+/// The result of [`UnixClock::try_set_frequency`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyChange {
+    /// The time at which the change was applied
+    pub applied_at: Timestamp,
+    /// The frequency that was applied, in the unit of [`Clock::set_frequency`]
+    pub frequency: f64,
+    /// Whether the requested frequency was clamped to the range that the kernel accepts
+    pub clamped: bool,
+}
+
+#[cfg(target_os = "linux")]
+const SIOCETHTOOL: libc::c_ulong = 0x8946;
+#[cfg(target_os = "linux")]
+const ETHTOOL_GET_TS_INFO: u32 = 0x41;
+
+/// `struct ethtool_ts_info` from `linux/ethtool.h`
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Debug, Default)]
+struct EthtoolTsInfo {
+    cmd: u32,
+    so_timestamping: u32,
+    phc_index: i32,
+    tx_types: u32,
+    tx_reserved: [u32; 3],
+    rx_filters: u32,
+    rx_reserved: [u32; 3],
+}
+
+/// The index of the clock device (PHC) of a network interface, as in `/dev/ptp<index>`.
+///
+/// Returns [`Error::NoDevice`] when the interface does not exist or has no clock device, and
+/// [`Error::Invalid`] when `name` is not a valid interface name.
+#[cfg(target_os = "linux")]
+pub fn phc_index_for_interface(name: &str) -> Result<i32, Error> {
+    let info = ethtool_ts_info(name)?;
+
+    if info.phc_index < 0 {
+        Err(Error::NoDevice)
+    } else {
+        Ok(info.phc_index)
+    }
+}
+
+/// The timestamping capabilities of a network interface.
+///
+/// Returns [`Error::NoDevice`] when the interface does not exist, and [`Error::Invalid`] when
+/// `name` is not a valid interface name.
+#[cfg(target_os = "linux")]
+pub fn timestamping_info(name: &str) -> Result<TimestampingInfo, Error> {
+    ethtool_ts_info(name).map(|info| TimestampingInfo::from_ethtool(&info))
+}
+
+#[cfg(target_os = "linux")]
+fn ethtool_ts_info(name: &str) -> Result<EthtoolTsInfo, Error> {
+    let name = name.as_bytes();
+    if name.is_empty() || name.len() >= libc::IFNAMSIZ || name.contains(&0) {
+        return Err(Error::Invalid);
+    }
+
+    let mut info = EthtoolTsInfo {
+        cmd: ETHTOOL_GET_TS_INFO,
+        ..Default::default()
+    };
+
+    // # Safety
+    //
+    // ifreq is plain old data, for which all zeroes is a valid value
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in request.ifr_name.iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+    request.ifr_ifru.ifru_data = &mut info as *mut EthtoolTsInfo as *mut libc::c_char;
+
+    // # Safety
+    //
+    // socket has no preconditions, and we take ownership of the fd it returns
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    cerr(socket)?;
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+
+    // # Safety
+    //
+    // SIOCETHTOOL receives a valid ifreq pointer, whose data points to a valid ethtool_ts_info
+    cerr(unsafe { libc::ioctl(socket.as_raw_fd(), SIOCETHTOOL as _, &mut request) })?;
+
+    Ok(info)
+}
+
+#[cfg(target_os = "linux")]
+fn phc_device_path(index: i32) -> PathBuf {
+    PathBuf::from(format!("/dev/ptp{index}"))
+}
+
+/// A clock device (PHC) found by [`list_phc_devices`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhcDevice {
+    /// The path of the device, e.g. `/dev/ptp0`
+    pub path: PathBuf,
+    /// The index of the device, as in `/dev/ptp<index>`
+    pub index: i32,
+    /// The name the driver gives the clock, e.g. `e1000e PTP`
+    pub name: Option<String>,
+    /// The capabilities of the clock, `None` when the device could not be opened (usually because
+    /// that requires root)
+    pub capabilities: Option<Capabilities>,
+    /// The network interface the clock belongs to, if any
+    pub interface: Option<String>,
+}
+
+/// Enumerate the clock devices (PHCs) of this system, sorted by index.
+///
+/// The name and network interface of each clock are read from `/sys/class/ptp`, which works
+/// without permission to open the devices themselves.
+#[cfg(target_os = "linux")]
+pub fn list_phc_devices() -> Result<Vec<PhcDevice>, Error> {
+    let mut devices =
+        scan_phc_devices(Path::new("/dev"), Path::new("/sys/class/ptp")).map_err(error_from_io)?;
+
+    for device in &mut devices {
+        device.capabilities = UnixClock::open(&device.path)
+            .ok()
+            .and_then(|clock| clock.capabilities().ok());
+    }
+
+    Ok(devices)
+}
+
+#[cfg(target_os = "linux")]
+fn scan_phc_devices(dev: &Path, sysfs: &Path) -> std::io::Result<Vec<PhcDevice>> {
+    let mut devices = Vec::new();
+
+    for entry in std::fs::read_dir(dev)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+
+        let index = match file_name.to_str().and_then(|name| name.strip_prefix("ptp")) {
+            Some(index) if index.bytes().all(|b| b.is_ascii_digit()) => index.parse().ok(),
+            _ => None,
+        };
+
+        if let Some(index) = index {
+            let class = sysfs.join(&file_name);
+
+            let name = std::fs::read(class.join("clock_name"))
+                .ok()
+                .and_then(|name| decode_device_name(&name).ok());
+
+            // the network device of a PHC lists its interfaces in `device/net`
+            let interface = std::fs::read_dir(class.join("device").join("net"))
+                .ok()
+                .and_then(|mut entries| entries.next())
+                .and_then(|entry| entry.ok())
+                .and_then(|entry| entry.file_name().into_string().ok());
+
+            devices.push(PhcDevice {
+                path: entry.path(),
+                index,
+                name,
+                capabilities: None,
+                interface,
+            });
+        }
+    }
+
+    devices.sort_by_key(|device| device.index);
+
+    Ok(devices)
+}
+
+/// Decode a (fixed size) name buffer, which ends at the first null byte if there is one
+#[cfg(target_os = "linux")]
+fn decode_device_name(buffer: &[u8]) -> Result<String, Error> {
+    let end = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    let name = std::str::from_utf8(&buffer[..end]).map_err(|_| Error::Invalid)?;
+
+    Ok(name.trim().to_owned())
+}
+
+/// Read an external timestamp event, `None` when a non-blocking fd has no event available
+#[cfg(target_os = "linux")]
+fn try_read_external_timestamp(fd: RawFd) -> Result<Option<PtpExtTsEvent>, Error> {
+    let mut event = PtpExtTsEventRaw::default();
+    let size = std::mem::size_of::<PtpExtTsEventRaw>();
+
+    loop {
+        // # Safety
+        //
+        // The buffer is valid for writes of `size` bytes
+        let n = unsafe { libc::read(fd, &mut event as *mut _ as *mut libc::c_void, size) };
+
+        match n {
+            -1 => match error_number() {
+                libc::EINTR => continue,
+                libc::EAGAIN => return Ok(None),
+                _ => return Err(convert_errno()),
+            },
+            n if n as usize == size => return Ok(Some(PtpExtTsEvent::from_raw(&event))),
+            // the kernel only ever hands out whole events
+            _ => return Err(Error::Invalid),
+        }
+    }
+}
+
+/// Wait until `fd` is readable, returning `false` when `timeout` passes first
+#[cfg(target_os = "linux")]
+fn poll_readable(fd: RawFd, timeout: Duration) -> Result<bool, Error> {
+    let deadline = std::time::Instant::now() + timeout;
+
+    loop {
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        // round up, so we never wake up before the timeout
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let millis = (remaining.as_nanos() + 999_999) / 1_000_000;
+        let millis = millis.min(libc::c_int::MAX as u128) as libc::c_int;
+
+        // # Safety
+        //
+        // We pass a single, valid pollfd
+        match unsafe { libc::poll(&mut pollfd, 1, millis) } {
+            // interrupted by a signal, wait for the rest of the timeout
+            -1 if error_number() == libc::EINTR => continue,
+            -1 => return Err(convert_errno()),
+            0 => return Ok(false),
+            _ => return Ok(true),
+        }
+    }
+}
+
+/// Collect events from `read` until it reports that none are available, or `max` are collected
+#[cfg(target_os = "linux")]
+fn drain_events<T>(
+    max: usize,
+    mut read: impl FnMut() -> Result<Option<T>, Error>,
+) -> Result<Vec<T>, Error> {
+    let mut events = Vec::new();
+
+    while events.len() < max {
+        match read()? {
+            Some(event) => events.push(event),
+            None => break,
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(target_os = "linux")]
+fn set_fd_nonblocking(fd: RawFd, nonblocking: bool) -> Result<(), Error> {
+    // # Safety
+    //
+    // fcntl with F_GETFL and F_SETFL has no memory safety preconditions
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    cerr(flags)?;
+
+    let flags = if nonblocking {
+        flags | libc::O_NONBLOCK
+    } else {
+        flags & !libc::O_NONBLOCK
+    };
+
+    cerr(unsafe { libc::fcntl(fd, libc::F_SETFL, flags) })
+}
+
+/// Whether `CAP_SYS_TIME` is among the effective capabilities in the contents of
+/// `/proc/self/status`, `None` when they are not listed.
+#[cfg(target_os = "linux")]
+fn has_cap_sys_time(status: &str) -> Option<bool> {
+    // from the kernel's include/uapi/linux/capability.h
+    const CAP_SYS_TIME: u32 = 25;
+
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))?;
+    let capabilities = u64::from_str_radix(line.trim(), 16).ok()?;
+
+    Some(capabilities & (1 << CAP_SYS_TIME) != 0)
+}
+
+/// The dynamic clock id of a clock device, see `FD_TO_CLOCKID` in the kernel's
+/// `posix-timers.h`.
+#[cfg(target_os = "linux")]
+fn clock_id_from_fd(fd: RawFd) -> libc::clockid_t {
+    ((!(fd as libc::clockid_t)) << 3) | 3
+}
+
+/// Read [`UnixClock::CLOCK_MONOTONIC`] and [`UnixClock::CLOCK_REALTIME`] at (nearly) the same
+/// moment, to convert between the two.
+///
+/// The realtime clock is read between two monotonic reads. The monotonic time is the midpoint of
+/// those reads, and the uncertainty is half the time between them.
+pub fn realtime_from_monotonic_pair() -> Result<TimePair, Error> {
+    let before = UnixClock::CLOCK_MONOTONIC.clock_gettime()?;
+    let realtime = UnixClock::CLOCK_REALTIME.clock_gettime()?;
+    let after = UnixClock::CLOCK_MONOTONIC.clock_gettime()?;
+
+    let before = current_time_timespec(before, Precision::Nano);
+    let realtime = current_time_timespec(realtime, Precision::Nano);
+    let after = current_time_timespec(after, Precision::Nano);
+
+    // the monotonic clock never goes backwards
+    let uncertainty = after.duration_since(&before).unwrap_or_default() / 2;
+
+    Ok(TimePair {
+        monotonic: before + uncertainty,
+        realtime,
+        uncertainty,
+    })
+}
+
+/// Matching readings of two clocks, see [`realtime_from_monotonic_pair`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimePair {
+    monotonic: Timestamp,
+    realtime: Timestamp,
+    uncertainty: Duration,
+}
+
+impl TimePair {
+    /// The time of the monotonic clock
+    pub fn monotonic(&self) -> Timestamp {
+        self.monotonic
+    }
+
+    /// The time of the realtime clock
+    pub fn realtime(&self) -> Timestamp {
+        self.realtime
+    }
+
+    /// How far apart the two readings may be
+    pub fn uncertainty(&self) -> Duration {
+        self.uncertainty
+    }
+}
+
+/// A clock reading with an uncertainty, see [`UnixClock::now_sampled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SampledTime {
+    timestamp: Timestamp,
+    uncertainty: Duration,
+}
+
+impl SampledTime {
+    /// The time that was read
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    /// How long reading the time took
+    pub fn uncertainty(&self) -> Duration {
+        self.uncertainty
+    }
+}
+
+/// The operations that a clock supports, see [`UnixClock::supported_operations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SupportedOps {
+    /// [`Clock::now`] and [`Clock::resolution`]
+    pub read: bool,
+    /// [`Clock::set_frequency`] and [`Clock::get_frequency`]
+    pub frequency: bool,
+    /// [`Clock::step_clock`]
+    pub step: bool,
+    /// [`Clock::set_leap_seconds`]
+    pub leap_seconds: bool,
+    /// [`Clock::set_tai`] and [`Clock::get_tai`]
+    pub tai: bool,
+    /// [`Clock::error_estimate_update`], which is silently ignored when unsupported
+    pub error_estimate: bool,
+    /// The kernel clock discipline: offset slewing, PPS and [`Clock::disable_kernel_ntp_algorithm`]
+    pub kernel_discipline: bool,
+}
+
+impl SupportedOps {
+    const READ_ONLY: Self = Self {
+        read: true,
+        frequency: false,
+        step: false,
+        leap_seconds: false,
+        tai: false,
+        error_estimate: false,
+        kernel_discipline: false,
+    };
+
+    const KERNEL: Self = Self {
+        read: true,
+        frequency: true,
+        step: true,
+        leap_seconds: true,
+        tai: true,
+        error_estimate: true,
+        kernel_discipline: true,
+    };
+
+    #[cfg(target_os = "linux")]
+    const DEVICE: Self = Self {
+        frequency: true,
+        step: true,
+        ..Self::READ_ONLY
+    };
+}
+
+/// Capabilities of a clock, see [`UnixClock::capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Capabilities {
+    /// Maximum frequency adjustment in parts per billion.
+    pub max_adj: i32,
+    /// Number of programmable alarms.
+    pub n_alarm: i32,
+    /// Number of external timestamp channels.
+    pub n_ext_ts: i32,
+    /// Number of programmable periodic signals.
+    pub n_per_out: i32,
+    /// Number of input/output pins.
+    pub n_pins: i32,
+    /// Whether the clock supports a PPS callback.
+    pub pps: bool,
+    /// Whether the clock supports precise system-device cross timestamps.
+    pub cross_timestamping: bool,
+}
+
+impl Capabilities {
+    /// The NTP kernel API accepts frequency adjustments of up to 500 ppm.
+    const NTP_KAPI: Self = Self {
+        max_adj: 500_000,
+        n_alarm: 0,
+        n_ext_ts: 0,
+        n_per_out: 0,
+        n_pins: 0,
+        pps: false,
+        cross_timestamping: false,
+    };
+
+    #[cfg(target_os = "linux")]
+    fn from_ptp_clock_caps(caps: &libc::ptp_clock_caps) -> Self {
+        Self {
+            max_adj: caps.max_adj,
+            n_alarm: caps.n_alarm,
+            n_ext_ts: caps.n_ext_ts,
+            n_per_out: caps.n_per_out,
+            n_pins: caps.n_pins,
+            pps: caps.pps != 0,
+            cross_timestamping: caps.cross_timestamping != 0,
+        }
+    }
+
+    /// Maximum frequency adjustment in parts per million, the unit of
+    /// [`Clock::set_frequency`].
+    pub fn max_frequency_ppm(&self) -> f64 {
+        self.max_adj as f64 / 1000.0
+    }
+}
+
+/// How much further a clock moved from `before` to `after` than the step by `offset`. This
+/// includes the time that passed between the two readings.
+fn step_error(before: Timestamp, after: Timestamp, offset: TimeOffset) -> SignedDuration {
+    let offset = i128::from(offset.seconds) * 1_000_000_000 + i128::from(offset.nanos);
+    let expected = Timestamp::from_nanos_since_epoch(before.as_nanos_since_epoch() + offset);
+
+    expected.offset_to(&after)
+}
+
+/// The rate of the device clock relative to the system clock between two
+/// [`UnixClock::system_offset`] samples, or `None` when the system clock did not advance.
+#[cfg(target_os = "linux")]
+fn frequency_ratio(
+    first: (Timestamp, Timestamp, Timestamp),
+    second: (Timestamp, Timestamp, Timestamp),
+) -> Option<f64> {
+    // the device was read halfway between the reads of the system clock
+    let system_time = |(before, _, after): (Timestamp, Timestamp, Timestamp)| {
+        (before.as_nanos_since_epoch() + after.as_nanos_since_epoch()) / 2
+    };
+
+    let system_elapsed = system_time(second) - system_time(first);
+    let device_elapsed = second.1.as_nanos_since_epoch() - first.1.as_nanos_since_epoch();
+
+    (system_elapsed > 0).then(|| device_elapsed as f64 / system_elapsed as f64)
+}
+
+// The clock states returned by the NTP kernel API, which libc does not define on all platforms
+const TIME_OK: libc::c_int = 0;
+const TIME_INS: libc::c_int = 1;
+const TIME_DEL: libc::c_int = 2;
+const TIME_OOP: libc::c_int = 3;
+const TIME_WAIT: libc::c_int = 4;
+const TIME_ERROR: libc::c_int = 5;
+
+/// The leap second state of a clock, as reported by the NTP kernel API (the `TIME_*` constants).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClockState {
+    /// No leap second is pending (`TIME_OK`)
+    Ok,
+    /// A leap second will be inserted at the end of the day (`TIME_INS`)
+    InsertPending,
+    /// A leap second will be deleted at the end of the day (`TIME_DEL`)
+    DeletePending,
+    /// A leap second is being inserted right now (`TIME_OOP`)
+    InProgress,
+    /// A leap second has just been inserted or deleted (`TIME_WAIT`)
+    LeapOccurred,
+    /// The clock is not synchronized (`TIME_ERROR`, also known as `TIME_BAD`)
+    Unsynchronized,
+}
+
+impl ClockState {
+    fn from_raw(state: libc::c_int) -> Self {
+        match state {
+            TIME_OK => Self::Ok,
+            TIME_INS => Self::InsertPending,
+            TIME_DEL => Self::DeletePending,
+            TIME_OOP => Self::InProgress,
+            TIME_WAIT => Self::LeapOccurred,
+            TIME_ERROR => Self::Unsynchronized,
+            // not returned by any kernel we know of, but treat it as an error to be safe
+            _ => Self::Unsynchronized,
+        }
+    }
+
+    /// Whether a leap second is announced for the end of the day, or is happening right now
+    pub fn leap_pending(&self) -> bool {
+        matches!(
+            self,
+            Self::InsertPending | Self::DeletePending | Self::InProgress
+        )
+    }
+}
+
+/// The TAI offset and leap second state of a clock, see [`UnixClock::tai_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaiInfo {
+    /// The offset between TAI and UTC, in seconds
+    pub tai: i32,
+    /// The leap second state of the clock
+    pub state: ClockState,
+}
+
+/// A leap second that the kernel is going to apply, see [`UnixClock::scheduled_leap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduledLeap {
+    /// [`LeapIndicator::Leap61`] for an insertion, [`LeapIndicator::Leap59`] for a deletion
+    pub indicator: LeapIndicator,
+    /// Whether the leap second is being inserted right now
+    pub in_progress: bool,
+    /// The end of the UTC day, at which the leap second applies. Only known for clocks in UTC.
+    pub at: Option<Timestamp>,
+}
+
+impl ScheduledLeap {
+    // see https://github.com/rust-lang/libc/issues/1848
+    #[cfg_attr(target_env = "musl", allow(deprecated))]
+    const SECONDS_PER_DAY: libc::time_t = 86400;
+
+    fn decode(
+        status: libc::c_int,
+        state: ClockState,
+    ) -> Result<Option<(LeapIndicator, bool)>, Error> {
+        let leap = match state {
+            ClockState::InsertPending => (LeapIndicator::Leap61, false),
+            ClockState::DeletePending => (LeapIndicator::Leap59, false),
+            ClockState::InProgress => (LeapIndicator::Leap61, true),
+            // the indicator stays set until it is cleared, but the leap second has been applied
+            ClockState::LeapOccurred => return Ok(None),
+            // the kernel picks up the indicator at the next tick, and does not report the leap
+            // second state while unsynchronized
+            ClockState::Ok | ClockState::Unsynchronized => {
+                match LeapIndicator::from_status_bits(status)? {
+                    indicator @ (LeapIndicator::Leap61 | LeapIndicator::Leap59) => {
+                        (indicator, false)
+                    }
+                    LeapIndicator::NoWarning | LeapIndicator::Unknown => return Ok(None),
+                }
+            }
+        };
+
+        Ok(Some(leap))
+    }
+
+    fn end_of_day(now: Timestamp) -> Timestamp {
+        let day = now.seconds.div_euclid(Self::SECONDS_PER_DAY);
+
+        Timestamp {
+            seconds: (day + 1) * Self::SECONDS_PER_DAY,
+            nanos: 0,
+        }
+    }
+}
+
+/// A leap second that is spread out over a window around midnight UTC, see
+/// [`UnixClock::smear_leap`].
+#[derive(Debug, Clone, Copy)]
+pub struct LeapSmear<'a> {
+    clock: &'a UnixClock,
+    direction: LeapIndicator,
+    window: Duration,
+    /// The midnight at the center of the window
+    at: Timestamp,
+    base_frequency: f64,
+}
+
+impl LeapSmear<'_> {
+    /// The frequency (in ppm) of the clock outside of the window, e.g. the frequency that the
+    /// clock discipline settled on. The smear is applied on top of it. Defaults to 0.
+    pub fn with_base_frequency(mut self, frequency: f64) -> Self {
+        self.base_frequency = frequency;
+        self
+    }
+
+    /// The midnight UTC at which the leap second happens, at the center of the window
+    pub fn at(&self) -> Timestamp {
+        self.at
+    }
+
+    /// The frequency offset (in ppm) of the smear within the window
+    fn rate(&self) -> f64 {
+        // an inserted second makes the day longer, so the clock must run slower
+        match self.direction {
+            LeapIndicator::Leap61 => -1e6 / self.window.as_secs_f64(),
+            LeapIndicator::Leap59 => 1e6 / self.window.as_secs_f64(),
+            LeapIndicator::NoWarning | LeapIndicator::Unknown => 0.0,
+        }
+    }
+
+    /// The frequency offset (in ppm) of the smear at time `now`. It is constant within the
+    /// window around [`LeapSmear::at`], and 0 outside of it.
+    pub fn frequency_offset(&self, now: Timestamp) -> f64 {
+        let since_leap = now.as_nanos_since_epoch() - self.at.as_nanos_since_epoch();
+        let half_window = i128::try_from(self.window.as_nanos() / 2).unwrap_or(i128::MAX);
+
+        if (-half_window..half_window).contains(&since_leap) {
+            self.rate()
+        } else {
+            0.0
+        }
+    }
+
+    /// Set the frequency of the clock for time `now`: the base frequency plus the smear.
+    /// Returns the time at which the frequency was changed.
+    ///
+    /// Returns [`Error::Invalid`], without changing the frequency, when the sum is beyond the
+    /// frequency limit of the clock: the clock would silently clamp it, and the smear would not
+    /// add up to a second.
+    pub fn update(&self, now: Timestamp) -> Result<Timestamp, Error> {
+        let frequency = self.base_frequency + self.frequency_offset(now);
+
+        if frequency.is_nan() || frequency.abs() > self.clock.frequency_limit_ppm()? {
+            return Err(Error::Invalid);
+        }
+
+        self.clock.set_frequency(frequency)
+    }
+}
+
+/// The kernel status flags of a clock (the `status` field of [`libc::timex`]).
+///
+/// Hardware clocks generally do not maintain these flags, and report all of them as unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ClockStatus {
+    bits: libc::c_int,
+}
+
+impl ClockStatus {
+    /// Interpret the `status` field of a [`libc::timex`].
+    pub fn from_bits(bits: libc::c_int) -> Self {
+        Self { bits }
+    }
+
+    /// The raw status bits.
+    pub fn bits(&self) -> libc::c_int {
+        self.bits
+    }
+
+    fn contains(&self, flag: libc::c_int) -> bool {
+        self.bits & flag != 0
+    }
+
+    /// [`libc::STA_PLL`]: kernel phase-locked loop updates are enabled.
+    pub fn pll(&self) -> bool {
+        self.contains(libc::STA_PLL)
+    }
+
+    /// [`libc::STA_PPSFREQ`]: pulse-per-second frequency discipline is enabled.
+    pub fn pps_frequency(&self) -> bool {
+        self.contains(libc::STA_PPSFREQ)
+    }
+
+    /// [`libc::STA_PPSTIME`]: pulse-per-second time discipline is enabled.
+    pub fn pps_time(&self) -> bool {
+        self.contains(libc::STA_PPSTIME)
+    }
+
+    /// [`libc::STA_FLL`]: the kernel uses a frequency-locked loop instead of a phase-locked loop.
+    pub fn fll(&self) -> bool {
+        self.contains(libc::STA_FLL)
+    }
+
+    /// [`libc::STA_INS`]: a leap second will be inserted at the end of the UTC day.
+    pub fn insert_leap(&self) -> bool {
+        self.contains(libc::STA_INS)
+    }
+
+    /// [`libc::STA_DEL`]: a leap second will be deleted at the end of the UTC day.
+    pub fn delete_leap(&self) -> bool {
+        self.contains(libc::STA_DEL)
+    }
+
+    /// [`libc::STA_UNSYNC`]: the clock is not synchronized.
+    pub fn unsynchronized(&self) -> bool {
+        self.contains(libc::STA_UNSYNC)
+    }
+
+    /// [`libc::STA_FREQHOLD`]: the frequency is held, and not updated by the kernel loops.
+    pub fn frequency_hold(&self) -> bool {
+        self.contains(libc::STA_FREQHOLD)
+    }
+
+    /// [`libc::STA_PPSSIGNAL`]: a valid pulse-per-second signal is present.
+    pub fn pps_signal(&self) -> bool {
+        self.contains(libc::STA_PPSSIGNAL)
+    }
+
+    /// [`libc::STA_PPSJITTER`]: the pulse-per-second signal jitter exceeds the limit.
+    pub fn pps_jitter(&self) -> bool {
+        self.contains(libc::STA_PPSJITTER)
+    }
+
+    /// [`libc::STA_PPSWANDER`]: the pulse-per-second signal wander exceeds the limit.
+    pub fn pps_wander(&self) -> bool {
+        self.contains(libc::STA_PPSWANDER)
+    }
+
+    /// [`libc::STA_PPSERROR`]: the pulse-per-second signal has a calibration error.
+    pub fn pps_error(&self) -> bool {
+        self.contains(libc::STA_PPSERROR)
+    }
+
+    /// [`libc::STA_CLOCKERR`]: the clock hardware reports a fault.
+    pub fn clock_error(&self) -> bool {
+        self.contains(libc::STA_CLOCKERR)
+    }
+
+    /// [`libc::STA_NANO`]: the kernel reports times in nanoseconds rather than microseconds.
+    pub fn nanosecond(&self) -> bool {
+        self.contains(libc::STA_NANO)
+    }
+}
+
+impl Timestamp {
+    /// Convert into a [`libc::timespec`], e.g. to pass it to `clock_nanosleep`.
+    pub fn as_timespec(&self) -> libc::timespec {
+        libc::timespec {
+            tv_sec: self.seconds,
+            tv_nsec: self.nanos as _,
+        }
+    }
+
+    /// Convert from a [`libc::timespec`].
+    ///
+    /// Out-of-range (including negative) nanoseconds are normalized into the seconds.
+    pub fn from_timespec(timespec: libc::timespec) -> Timestamp {
+        current_time_timespec(timespec, Precision::Nano)
+    }
+}
+
+/// Errors that can be thrown by modifying a unix clock
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// Insufficient permissions to interact with the clock.
+    NoPermission,
+    /// No access to the clock.
+    NoAccess,
+    /// Invalid operation requested
+    Invalid,
+    /// Clock device has gone away
+    NoDevice,
+    /// Clock operation requested is not supported by operating system.
+    ///
+    /// This does not change at runtime: the operation is never available for this kind of
+    /// clock on this platform. The OS equivalent is `ENOSYS`.
+    NotSupported,
+    /// The kernel or the clock device reported that it does not support the requested operation
+    /// (`EOPNOTSUPP` or `ENOTTY`). Another device may support it.
+    OperationUnsupported,
+    /// Nothing happened before the timeout, see [`UnixClock::wait_for_pps`]
+    TimedOut,
+    /// The clock did not move by the requested step, see [`UnixClock::step_clock_verified`]
+    StepMismatch,
+    /// Any other error, with the raw error code of the operating system
+    Other(i32),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Error::*;
+
+        let msg = match self {
+            Other(code) => {
+                let error = std::io::Error::from_raw_os_error(*code);
+                return write!(f, "Unexpected clock error: {error}");
+            }
+            NoPermission => "Insufficient permissions to interact with the clock.",
+            NoAccess => "No access to the clock.",
+            Invalid => "Invalid operation requested",
+            NoDevice => "Clock device has gone away",
+            NotSupported => "Clock operation requested is not supported by operating system.",
+            OperationUnsupported => "Clock operation requested is not supported by the clock.",
+            TimedOut => "Timed out waiting for the clock.",
+            StepMismatch => "The clock did not step by the requested offset.",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Turn the `Error::NotSupported` and `Error::OperationUnsupported` error variants into
+    /// `Ok(())`, to silently ignore operations that are not supported by the current clock. All
+    /// other input values are untouched.
+    pub fn ignore_not_supported(res: Result<(), Error>) -> Result<(), Error> {
+        match res {
+            Err(Error::NotSupported | Error::OperationUnsupported) => Ok(()),
+            other => other,
+        }
+    }
+
+    // TODO: use https://doc.rust-lang.org/std/io/type.RawOsError.html when stable
+    fn into_raw_os_error(self) -> i32 {
+        match self {
+            Self::NoPermission => libc::EPERM,
+            Self::NoAccess => libc::EACCES,
+            Self::Invalid => libc::EINVAL,
+            Self::NoDevice => libc::ENODEV,
+            // EOPNOTSUPP is the same as ENOTSUP on Linux, so the platform gets a code of its own
+            Self::NotSupported => libc::ENOSYS,
+            Self::OperationUnsupported => libc::EOPNOTSUPP,
+            Self::TimedOut => libc::ETIMEDOUT,
+            Self::StepMismatch => libc::EIO,
+            Self::Other(errno) => errno,
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        std::io::Error::from_raw_os_error(value.into_raw_os_error())
+    }
+}
+
+impl<'a> TryFrom<&'a std::io::Error> for Error {
+    type Error = &'a std::io::Error;
+
+    /// Recover the clock error from an [`std::io::Error`], which fails (returning the input) when
+    /// it has no OS error code. This is the inverse of the conversion into an
+    /// [`std::io::Error`], except that [`Error::StepMismatch`] comes back as
+    /// `Error::Other(libc::EIO)`.
+    fn try_from(error: &'a std::io::Error) -> Result<Self, Self::Error> {
+        error.raw_os_error().map(error_from_errno).ok_or(error)
+    }
+}
+
+fn error_number() -> libc::c_int {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        *libc::__errno_location()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    unsafe {
+        *libc::__error()
+    }
+}
+
+// Convert those error numbers that can occur for calls to the following
+// functions
+// - ntp_adjtimex https://man7.org/linux/man-pages/man3/ntp_adjtime.3.html
+// - clock_gettime & clock_settime https://man7.org/linux/man-pages/man3/clock_gettime.3.html
+fn convert_errno() -> Error {
+    error_from_errno(error_number())
+}
+
+fn error_from_errno(errno: libc::c_int) -> Error {
+    match errno {
+        libc::EINVAL => Error::Invalid,
+        // The documentation is a bit unclear if this can happen with
+        // non-dynamic clocks like the ntp kapi clock, however deal with it just in case.
+        libc::ENODEV => Error::NoDevice,
+        libc::EOPNOTSUPP => Error::OperationUnsupported,
+        // the kernel does not implement the call at all
+        libc::ENOSYS => Error::NotSupported,
+        libc::EPERM => Error::NoPermission,
+        libc::EACCES => Error::NoAccess,
+        // an ioctl that the device does not implement
+        libc::ENOTTY => Error::OperationUnsupported,
+        libc::ETIMEDOUT => Error::TimedOut,
+        // we always pass in valid (accessible) buffers, but sandboxes may still report this
+        libc::EFAULT => Error::Invalid,
+        // No other errors should occur, but don't bring down the process if they do
+        other => Error::Other(other),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn error_from_io(error: std::io::Error) -> Error {
+    error
+        .raw_os_error()
+        .map_or(Error::Invalid, error_from_errno)
+}
+
+fn cerr(c_int: libc::c_int) -> Result<(), Error> {
+    if c_int == -1 {
+        Err(convert_errno())
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) enum Precision {
+    Nano,
+    Micro,
+}
+
+impl Precision {
+    /// The unit of the times and offsets in a [`libc::timex`] with these status flags
+    fn from_status(status: libc::c_int) -> Self {
+        if ClockStatus::from_bits(status).nanosecond() {
+            Precision::Nano
+        } else {
+            Precision::Micro
+        }
+    }
+}
+
+/// Convert to an i64, saturating at its minimum and maximum
+fn saturating_i64(value: i128) -> i64 {
+    value.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
+
+/// Bring the tv_nsec of a timespec into [0, 1e9), carrying into or borrowing from tv_sec
+#[cfg(any(not(target_os = "linux"), test))]
+fn normalize_timespec(mut timespec: libc::timespec) -> libc::timespec {
+    while timespec.tv_nsec >= 1_000_000_000 {
+        timespec.tv_sec += 1;
+        timespec.tv_nsec -= 1_000_000_000;
+    }
+
+    while timespec.tv_nsec < 0 {
+        timespec.tv_sec -= 1;
+        timespec.tv_nsec += 1_000_000_000;
+    }
+
+    timespec
+}
+
+/// Convert a time read from the kernel, whose sub-second field is in the unit of `precision`.
+///
+/// No kernel interface (including `ptp_clock_time` of the precise PTP offset ioctls) reports
+/// fractions of a nanosecond, so nothing is lost here.
+fn current_time_timespec(timespec: libc::timespec, precision: Precision) -> Timestamp {
+    let mut seconds = timespec.tv_sec;
+
+    let nanos: i32 = timespec.tv_nsec as _;
+
+    let mut nanos = match precision {
+        Precision::Nano => nanos,
+        Precision::Micro => nanos.checked_mul(1000).unwrap_or_default(),
+    };
+
+    // on macOS (at least) we've observed higher nanosecond counts than appear valid
+    while nanos >= 1_000_000_000 {
+        seconds = seconds.wrapping_add(1);
+        nanos -= 1_000_000_000;
+    }
+
+    // we disallow negative nanoseconds
+    while nanos < 0 {
+        seconds = seconds.wrapping_sub(1);
+        nanos += 1_000_000_000;
+    }
+
+    Timestamp {
+        seconds,
+        nanos: nanos as u32,
+    }
+}
+
+/// Like [`current_time_timespec`], for the `time` of a [`libc::timex`], where `tv_usec` holds
+/// nanoseconds when the kernel runs in nanosecond mode.
+#[cfg_attr(not(target_os = "linux"), allow(unused))]
+fn current_time_timeval(timespec: libc::timeval, precision: Precision) -> Timestamp {
+    let seconds = timespec.tv_sec;
+    let nanos = match precision {
+        Precision::Nano => timespec.tv_usec as u32,
+        Precision::Micro => (timespec.tv_usec as u32)
+            .checked_mul(1000)
+            .unwrap_or_default(),
+    };
+
+    // like for timespecs, do not trust the kernel to keep the sub-second part below a second
+    Timestamp { seconds, nanos }.normalized()
+}
+
+// The error estimates the kernel starts out with, and resets to when the clock is unsynchronized
+// (NTP_PHASE_LIMIT, 16 seconds)
+const UNKNOWN_ERROR_MICROS: libc::c_long = 16_000_000;
+
+// Since Linux 2.6.26, the frequency of the NTP kernel API is clamped to the range (-32768000,
+// +32768000). In older kernels, an EINVAL error occurs if the supplied value is out of range.
+// (32768000 is 500 << 16)
+const NTP_KAPI_MAX_FREQUENCY: i64 = 32_768_000 - 1;
+
+const EMPTY_TIMESPEC: libc::timespec = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+};
+
+// Libc has no good other way of obtaining this, so let's at least make our
+// functions more readable.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub const EMPTY_TIMEX: libc::timex = libc::timex {
+    modes: 0,
+    offset: 0,
+    freq: 0,
+    maxerror: 0,
+    esterror: 0,
+    status: 0,
+    constant: 0,
+    precision: 0,
+    tolerance: 0,
+    time: libc::timeval {
+        tv_sec: 0,
+        tv_usec: 0,
+    },
+    tick: 0,
+    ppsfreq: 0,
+    jitter: 0,
+    shift: 0,
+    stabil: 0,
+    jitcnt: 0,
+    calcnt: 0,
+    errcnt: 0,
+    stbcnt: 0,
+    tai: 0,
+    __unused1: 0,
+    __unused2: 0,
+    __unused3: 0,
+    __unused4: 0,
+    __unused5: 0,
+    __unused6: 0,
+    __unused7: 0,
+    __unused8: 0,
+    __unused9: 0,
+    __unused10: 0,
+    __unused11: 0,
+};
+
+#[cfg(all(target_os = "linux", target_env = "musl"))]
+pub const EMPTY_TIMEX: libc::timex = libc::timex {
+    modes: 0,
+    offset: 0,
+    freq: 0,
+    maxerror: 0,
+    esterror: 0,
+    status: 0,
+    constant: 0,
+    precision: 0,
+    tolerance: 0,
+    time: libc::timeval {
+        tv_sec: 0,
+        tv_usec: 0,
+    },
+    tick: 0,
+    ppsfreq: 0,
+    jitter: 0,
+    shift: 0,
+    stabil: 0,
+    jitcnt: 0,
+    calcnt: 0,
+    errcnt: 0,
+    stbcnt: 0,
+    tai: 0,
+    __padding: [0; 11],
+};
+
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+pub const EMPTY_TIMEX: libc::timex = libc::timex {
+    modes: 0,
+    offset: 0,
+    freq: 0,
+    maxerror: 0,
+    esterror: 0,
+    status: 0,
+    constant: 0,
+    precision: 0,
+    tolerance: 0,
+    ppsfreq: 0,
+    jitter: 0,
+    shift: 0,
+    stabil: 0,
+    jitcnt: 0,
+    calcnt: 0,
+    errcnt: 0,
+    stbcnt: 0,
+};
+
+impl LeapIndicator {
+    fn as_status_bit(self) -> libc::c_int {
+        match self {
+            LeapIndicator::NoWarning => 0,
+            LeapIndicator::Leap61 => libc::STA_INS,
+            LeapIndicator::Leap59 => libc::STA_DEL,
+            LeapIndicator::Unknown => libc::STA_UNSYNC,
+        }
+    }
+
+    fn from_status_bits(status: libc::c_int) -> Result<Self, Error> {
+        let insert = status & libc::STA_INS != 0;
+        let delete = status & libc::STA_DEL != 0;
+
+        match (insert, delete) {
+            (true, true) => Err(Error::Invalid),
+            (true, false) => Ok(LeapIndicator::Leap61),
+            (false, true) => Ok(LeapIndicator::Leap59),
+            (false, false) if status & libc::STA_UNSYNC != 0 => Ok(LeapIndicator::Unknown),
+            (false, false) => Ok(LeapIndicator::NoWarning),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_now_does_not_crash() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        assert_ne!(clock.now().unwrap(), Timestamp::default(),);
+    }
+
+    #[test]
+    fn realtime_gettime() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let time = clock.clock_gettime().unwrap();
+
+        assert_ne!((time.tv_sec, time.tv_nsec), (0, 0))
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn ptp0_gettime() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let time = clock.clock_gettime().unwrap();
+
+        assert_ne!((time.tv_sec, time.tv_nsec), (0, 0))
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn error_estimate_update_returns_recent_time() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let (est_error, max_error) = clock.read_error_estimate().unwrap();
+
+        let before = clock.now().unwrap();
+        let applied = clock.error_estimate_update(est_error, max_error).unwrap();
+
+        let elapsed = applied.duration_since(&before).unwrap();
+        assert!(elapsed < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn ensure_frequency_skips_current_frequency() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let frequency = clock.get_frequency().unwrap();
+
+        // reading the frequency does not need permissions, but writing it would
+        assert_eq!(clock.ensure_frequency(frequency, 0.0), Ok(None));
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn ensure_frequency_writes_changed_frequency() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let frequency = clock.get_frequency().unwrap();
+
+        let changed = if frequency > 0.0 { -1.0 } else { 1.0 };
+        assert!(clock.ensure_frequency(changed, 0.5).unwrap().is_some());
+        assert!(clock.ensure_frequency(frequency, 0.5).unwrap().is_some());
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn swap_frequency_returns_old_frequency() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let frequency = clock.get_frequency().unwrap();
+
+        let (old, _) = clock.swap_frequency(frequency + 1.0).unwrap();
+        assert_eq!(old, frequency);
+
+        let (old, _) = clock.swap_frequency(frequency).unwrap();
+        assert_eq!(old, frequency + 1.0);
+    }
+
+    #[test]
+    fn frequency_from_timex() {
+        let timex = libc::timex {
+            freq: -(12 << 16) - (1 << 15),
+            ..EMPTY_TIMEX
+        };
+
+        assert_eq!(UnixClock::frequency_from_timex(&timex), -12.5);
+        assert_eq!(UnixClock::frequency_from_timex(&EMPTY_TIMEX), 0.0);
+
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.swap_frequency(1.0),
+            Err(Error::NotSupported)
+        );
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn reset_discipline() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let state = clock.capture_state().unwrap();
+
+        clock.set_frequency(12.5).unwrap();
+        clock.reset_discipline().unwrap();
+
+        let frequency = clock.get_frequency().unwrap();
+        let status = clock.status().unwrap();
+        clock.restore_state(&state).unwrap();
+
+        assert!(frequency.abs() < 1e-3);
+        assert!(status.pll());
+        assert!(!status.insert_leap() && !status.delete_leap());
+    }
+
+    #[test]
+    fn test_reset_discipline_timex() {
+        let timex = UnixClock::reset_discipline_timex();
+
+        assert_eq!(timex.modes & libc::MOD_OFFSET, libc::MOD_OFFSET);
+        #[cfg(target_os = "linux")]
+        assert_eq!(timex.modes & libc::ADJ_SETOFFSET, 0);
+        assert_eq!((timex.freq, timex.offset), (0, 0));
+
+        let status = ClockStatus::from_bits(timex.status);
+        assert!(status.pll());
+        assert!(status.unsynchronized());
+        assert!(!status.fll());
+        assert!(!status.insert_leap() && !status.delete_leap());
+    }
+
+    #[test]
+    #[cfg(target_os = "freebsd")]
+    fn precision_follows_sta_nano() {
+        let mut timex = EMPTY_TIMEX;
+        timex.jitter = 1_500;
+
+        // FreeBSD starts in microsecond mode
+        timex.status = libc::STA_PLL;
+        assert!(matches!(
+            Precision::from_status(timex.status),
+            Precision::Micro
+        ));
+        assert_eq!(
+            UnixClock::jitter_from_timex(&timex),
+            Duration::from_micros(1_500)
+        );
+
+        timex.status |= libc::STA_NANO;
+        assert!(matches!(
+            Precision::from_status(timex.status),
+            Precision::Nano
+        ));
+        assert_eq!(
+            UnixClock::jitter_from_timex(&timex),
+            Duration::from_nanos(1_500)
+        );
+    }
+
+    #[test]
+    fn test_raw_clock_id() {
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.raw_clock_id(),
+            libc::CLOCK_REALTIME
+        );
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.raw_clock_id(),
+            libc::CLOCK_MONOTONIC
+        );
+
+        #[cfg(target_os = "linux")]
+        {
+            let file = std::fs::File::open("/dev/null").unwrap();
+            let fd = file.as_raw_fd();
+            let clock = UnixClock::from_owned_fd(file.into());
+            assert_eq!(clock.raw_clock_id(), clock_id_from_fd(fd));
+        }
+    }
+
+    #[test]
+    fn resolution_is_cached() {
+        let reads = std::cell::Cell::new(0);
+        let read = || {
+            reads.set(reads.get() + 1);
+            Ok(Timestamp {
+                seconds: 0,
+                nanos: 4,
+            })
+        };
+
+        let cache = AtomicU64::new(UNREAD_RESOLUTION);
+        let first = cached_resolution_in(&cache, read).unwrap();
+        let second = cached_resolution_in(&cache, read).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(reads.get(), 1);
+
+        // a zero resolution is cached as well
+        let cache = AtomicU64::new(UNREAD_RESOLUTION);
+        let zero = || {
+            reads.set(reads.get() + 1);
+            Ok(Timestamp::default())
+        };
+        cached_resolution_in(&cache, zero).unwrap();
+        assert_eq!(cached_resolution_in(&cache, zero), Ok(Timestamp::default()));
+        assert_eq!(reads.get(), 2);
+
+        // clock devices have a cache of their own, which their clones share
+        #[cfg(target_os = "linux")]
+        {
+            let file = std::fs::File::open("/dev/null").unwrap();
+            let device = UnixClock::from_owned_fd(file.into());
+            let clone = device.clone();
+
+            let first = device.cached_resolution(read).unwrap();
+            let second = clone.cached_resolution(read).unwrap();
+            assert_eq!(first, second);
+            assert_eq!(reads.get(), 3);
+        }
+
+        assert!(kernel_resolution_cache(libc::CLOCK_MONOTONIC).is_some());
+        assert!(kernel_resolution_cache(libc::CLOCK_PROCESS_CPUTIME_ID).is_none());
+
+        let clock = UnixClock::CLOCK_MONOTONIC;
+        assert_eq!(clock.resolution(), clock.resolution_uncached());
+    }
+
+    #[test]
+    fn frequency_only_refuses_steps() {
+        let clock = UnixClock::CLOCK_REALTIME.with_frequency_only();
+        assert!(clock.is_frequency_only());
+        assert!(!clock.supported_operations().step);
+
+        let offset = TimeOffset {
+            seconds: 1,
+            nanos: 0,
+        };
+
+        // refused before the kernel is asked, so this needs no permissions
+        assert_eq!(clock.step_clock(offset), Err(Error::NotSupported));
+        assert_eq!(clock.step_to_next_second(), Err(Error::NotSupported));
+
+        let clone = clock.clone();
+        assert!(clone.is_frequency_only());
+        assert_eq!(clone.step_clock(offset), Err(Error::NotSupported));
+
+        // cannot be slewed either, rather than slewing only half a second of it
+        assert_eq!(
+            clock.correct_offset(offset, Duration::from_millis(125)),
+            Err(Error::Invalid)
+        );
+
+        assert!(!UnixClock::CLOCK_REALTIME.is_frequency_only());
+    }
+
+    #[test]
+    #[ignore = "requires permissions, useful for testing permissions"]
+    fn step_clock() {
+        UnixClock::CLOCK_REALTIME
+            .step_clock(TimeOffset {
+                seconds: 0,
+                nanos: 0,
+            })
+            .unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_step_clock() {
+        let offset = TimeOffset {
+            seconds: 1,
+            nanos: 200000000,
+        };
+        let timex = UnixClock::step_clock_timex(offset);
+
+        assert_eq!(timex.modes, libc::ADJ_SETOFFSET | libc::ADJ_NANO);
+
+        assert_eq!(timex.time.tv_sec, 1);
+        assert_eq!(timex.time.tv_usec, 200_000_000);
+
+        // ADJ_SETOFFSET requires a non-negative tv_usec, so -0.5 seconds is -1s + 0.5s
+        let offset = TimeOffset::from_duration_signed(0, -500_000_000);
+        let timex = UnixClock::step_clock_timex(offset);
+
+        assert_eq!(timex.time.tv_sec, -1);
+        assert_eq!(timex.time.tv_usec, 500_000_000);
+    }
+
+    #[test]
+    fn test_offset_timespec() {
+        let timespec = libc::timespec {
+            tv_sec: 10,
+            tv_nsec: 800_000_000,
+        };
+
+        // carries into the seconds, including an exact second of nanoseconds
+        let offset = TimeOffset {
+            seconds: 0,
+            nanos: 200_000_000,
+        };
+        let result = UnixClock::offset_timespec(timespec, offset);
+        assert_eq!((result.tv_sec, result.tv_nsec), (11, 0));
+
+        // -0.9 seconds is -1s + 0.1s
+        let offset = TimeOffset::from_duration_signed(0, -900_000_000);
+        let result = UnixClock::offset_timespec(timespec, offset);
+        assert_eq!((result.tv_sec, result.tv_nsec), (9, 900_000_000));
+    }
+
+    #[test]
+    fn test_normalize_timespec() {
+        let timespec = libc::timespec {
+            tv_sec: 10,
+            tv_nsec: -300_000_000,
+        };
+        let result = normalize_timespec(timespec);
+        assert_eq!((result.tv_sec, result.tv_nsec), (9, 700_000_000));
+
+        let timespec = libc::timespec {
+            tv_sec: -1,
+            tv_nsec: 2_000_000_001,
+        };
+        let result = normalize_timespec(timespec);
+        assert_eq!((result.tv_sec, result.tv_nsec), (1, 1));
+    }
+
+    #[test]
+    fn test_adjust_offset() {
+        let offset = TimeOffset {
+            seconds: 0,
+            nanos: 200_000,
+        };
+        let timex = UnixClock::adjust_offset_timex(offset).unwrap();
+
+        assert_eq!(timex.modes, libc::MOD_OFFSET | libc::MOD_NANO);
+        assert_eq!(timex.offset, 200_000);
+
+        // -0.25 seconds
+        let offset = TimeOffset {
+            seconds: -1,
+            nanos: 750_000_000,
+        };
+        let timex = UnixClock::adjust_offset_timex(offset).unwrap();
+        assert_eq!(timex.offset, -250_000_000);
+
+        // MAXPHASE itself is accepted
+        let offset = TimeOffset {
+            seconds: -1,
+            nanos: 500_000_000,
+        };
+        let timex = UnixClock::adjust_offset_timex(offset).unwrap();
+        assert_eq!(timex.offset, -500_000_000);
+
+        // the kernel would clamp anything beyond MAXPHASE
+        let offset = TimeOffset {
+            seconds: 0,
+            nanos: 500_000_001,
+        };
+        assert_eq!(
+            UnixClock::adjust_offset_timex(offset).unwrap_err(),
+            Error::Invalid
+        );
+
+        let offset = TimeOffset {
+            seconds: -2,
+            nanos: 0,
+        };
+        assert_eq!(
+            UnixClock::adjust_offset_timex(offset).unwrap_err(),
+            Error::Invalid
+        );
+        assert_eq!(
+            UnixClock::adjust_timex(0.0, offset, NTP_KAPI_MAX_FREQUENCY).unwrap_err(),
+            Error::Invalid
+        );
+    }
+
+    #[test]
+    fn test_frequency_ppb() {
+        for ppb in [0, 1, -1, 499, 12_345, -12_345, 250_000, -499_999] {
+            let scaled = UnixClock::scaled_ppm_from_ppb(ppb);
+            let timex = UnixClock::set_frequency_timex(ppb as f64 / 1000.0);
+            let freq: i64 = timex.freq as _;
+
+            assert_eq!(scaled, freq, "{ppb}");
+            assert_eq!(
+                UnixClock::ppb_from_scaled_ppm(scaled),
+                ((scaled as f64 / 65536.0) * 1000.0).round() as i64,
+                "{ppb}"
+            );
+        }
+
+        // 1 ppm is exactly 65536 units
+        assert_eq!(UnixClock::scaled_ppm_from_ppb(1000), 65536);
+        assert_eq!(UnixClock::ppb_from_scaled_ppm(-65536), -1000);
+
+        // rounding to the nearest ppb
+        assert_eq!(UnixClock::ppb_from_scaled_ppm(1), 0);
+        assert_eq!(UnixClock::ppb_from_scaled_ppm(33), 1);
+        assert_eq!(UnixClock::ppb_from_scaled_ppm(-33), -1);
+
+        // clamping still applies
+        let timex = UnixClock::set_scaled_frequency_timex_within(
+            UnixClock::scaled_ppm_from_ppb(600_000),
+            NTP_KAPI_MAX_FREQUENCY,
+        );
+        assert_eq!(timex.freq, 32_768_000 - 1);
+
+        // huge requests saturate in the requested direction, rather than overflowing
+        assert_eq!(UnixClock::scaled_ppm_from_ppb(i64::MAX), i64::MAX);
+        assert_eq!(UnixClock::scaled_ppm_from_ppb(i64::MIN), i64::MIN);
+        assert_eq!(
+            UnixClock::ppb_from_scaled_ppm(i64::MAX),
+            140_737_488_355_328_000
+        );
+        assert_eq!(
+            UnixClock::ppb_from_scaled_ppm(i64::MIN),
+            -140_737_488_355_328_000
+        );
+
+        for (ppb, limit) in [
+            (i64::MAX, NTP_KAPI_MAX_FREQUENCY),
+            (i64::MIN, -NTP_KAPI_MAX_FREQUENCY),
+        ] {
+            let timex = UnixClock::set_scaled_frequency_timex_within(
+                UnixClock::scaled_ppm_from_ppb(ppb),
+                NTP_KAPI_MAX_FREQUENCY,
+            );
+            let freq: i64 = timex.freq as _;
+            assert_eq!(freq, limit);
+        }
+    }
+
+    #[test]
+    fn test_frequency_clamping_reported() {
+        let timex = UnixClock::set_frequency_timex(600.0);
+        let (applied, clamped) = UnixClock::applied_frequency(600.0, &timex);
+
+        assert!(clamped);
+        assert!(applied < 500.0 && applied > 499.99);
+
+        let timex = UnixClock::set_frequency_timex(-600.0);
+        let (applied, clamped) = UnixClock::applied_frequency(-600.0, &timex);
+
+        assert!(clamped);
+        assert!(applied > -500.0 && applied < -499.99);
+
+        let timex = UnixClock::set_frequency_timex(12.5);
+        assert_eq!(UnixClock::applied_frequency(12.5, &timex), (12.5, false));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_frequency_limit_uses_device_max_adj() {
+        // a device that accepts up to 2000 ppm, with its capabilities already cached
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let clock = UnixClock {
+            clock: libc::CLOCK_REALTIME,
+            fd: Some(ClockFd::Owned(Arc::new(file.into()))),
+            cache: Some(Arc::new(DeviceCache {
+                max_adj: AtomicI32::new(2_000_000),
+                max_adj_read: AtomicBool::new(true),
+                ..DeviceCache::default()
+            })),
+            frequency_only: false,
+        };
+
+        let limit = clock.frequency_limit().unwrap();
+        assert_eq!(limit, 2000 << 16);
+
+        let timex = UnixClock::set_frequency_timex_within(1500.0, limit);
+        assert_eq!(timex.freq, 1500 << 16);
+
+        let timex = UnixClock::set_frequency_timex_within(-2500.0, limit);
+        assert_eq!(timex.freq, -(2000 << 16));
+
+        // clones of a device remain devices, and share what has been cached
+        let clone = clock.clone();
+        assert_eq!(clone.frequency_limit(), Ok(2000 << 16));
+
+        // never beyond the limit of the device, even when it is not a whole unit
+        assert_eq!(UnixClock::scaled_frequency_limit(999), 65_470);
+
+        // other clocks are limited by the kernel NTP API
+        let limit = UnixClock::CLOCK_REALTIME.frequency_limit().unwrap();
+        assert_eq!(limit, NTP_KAPI_MAX_FREQUENCY);
+
+        // a device that cannot adjust its frequency at all; /dev/null would fail the ioctl, so
+        // this shows that the cached 0 is used
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let clock = UnixClock::from_owned_fd(file.into());
+        assert!(clock.frequency_limit().is_err());
+
+        let cache = clock.cache.as_ref().unwrap();
+        cache.max_adj_read.store(true, Ordering::Release);
+        assert_eq!(clock.frequency_limit(), Ok(0));
+    }
+
+    #[test]
+    fn test_frequency_within_tolerance() {
+        let current = UnixClock::set_frequency_timex(12.5);
+
+        // the same frequency is never written again
+        let requested = UnixClock::set_frequency_timex(12.5);
+        assert!(UnixClock::frequency_within_tolerance(
+            &current, &requested, 0.0
+        ));
+
+        // a change is only written when it is larger than the tolerance
+        let requested = UnixClock::set_frequency_timex(12.6);
+        assert!(UnixClock::frequency_within_tolerance(
+            &current, &requested, 0.2
+        ));
+        assert!(!UnixClock::frequency_within_tolerance(
+            &current, &requested, 0.05
+        ));
+
+        let requested = UnixClock::set_frequency_timex(-12.5);
+        assert!(!UnixClock::frequency_within_tolerance(
+            &current, &requested, 20.0
+        ));
+
+        // the frequency is compared as the kernel would store it
+        let current = UnixClock::set_frequency_timex(600.0);
+        let requested = UnixClock::set_frequency_timex(700.0);
+        assert!(UnixClock::frequency_within_tolerance(
+            &current, &requested, 0.0
+        ));
+    }
+
+    #[test]
+    fn test_step_error() {
+        use crate::test_clock::MockClock;
+
+        let clock = MockClock::new(Timestamp {
+            seconds: 100,
+            nanos: 0,
+        });
+        let offset = TimeOffset::from_duration_signed(-5, 250_000_000);
+
+        // a step that landed, with some time passing between the readings
+        let before = clock.now().unwrap();
+        clock.step_clock(offset).unwrap();
+        clock.advance(Duration::from_micros(20));
+        let after = clock.now().unwrap();
+
+        let error = step_error(before, after, offset);
+        assert!(!error.negative);
+        assert_eq!(error.duration, Duration::from_micros(20));
+        assert!(error.duration <= UnixClock::STEP_TOLERANCE);
+
+        // a step that the clock silently ignored
+        let before = clock.now().unwrap();
+        clock.advance(Duration::from_micros(20));
+        let after = clock.now().unwrap();
+
+        let error = step_error(before, after, offset);
+        assert!(!error.negative);
+        assert!(error.duration > UnixClock::STEP_TOLERANCE);
+
+        // a step that was clamped to a smaller size
+        let before = clock.now().unwrap();
+        clock
+            .step_clock(TimeOffset::from_duration_signed(-1, 0))
+            .unwrap();
+        let after = clock.now().unwrap();
+
+        let error = step_error(before, after, offset);
+        assert!(!error.negative);
+        assert_eq!(error.duration, Duration::from_millis(3750));
+    }
+
+    #[test]
+    fn test_adjust() {
+        let offset = TimeOffset {
+            seconds: 0,
+            nanos: 1_000,
+        };
+        let timex = UnixClock::adjust_timex(12.5, offset, NTP_KAPI_MAX_FREQUENCY).unwrap();
+
+        assert_eq!(
+            timex.modes,
+            libc::MOD_FREQUENCY | libc::MOD_OFFSET | libc::MOD_NANO
+        );
+        assert_eq!(timex.freq, 12 * 65536 + 32768);
+        assert_eq!(timex.offset, 1_000);
+
+        // the frequency is clamped to the given limit
+        let timex = UnixClock::adjust_timex(12.5, offset, 10 << 16).unwrap();
+        assert_eq!(timex.freq, 10 << 16);
+
+        // a clock device would only apply the frequency, so both are refused up front
+        #[cfg(target_os = "linux")]
+        {
+            let file = std::fs::File::open("/dev/null").unwrap();
+            let clock = UnixClock::from_owned_fd(file.into());
+            assert_eq!(clock.adjust(12.5, offset), Err(Error::OperationUnsupported));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_set_tick() {
+        let timex = UnixClock::set_tick_timex(10_050);
+
+        assert_eq!(timex.modes, libc::ADJ_TICK);
+        assert_eq!(timex.tick, 10_050);
+
+        assert!(UnixClock::tick_in_range(10_000, 100));
+        assert!(UnixClock::tick_in_range(9_000, 100));
+        assert!(UnixClock::tick_in_range(11_000, 100));
+        assert!(!UnixClock::tick_in_range(8_999, 100));
+        assert!(!UnixClock::tick_in_range(11_001, 100));
+        assert!(UnixClock::tick_in_range(1_000, 1000));
+        assert!(!UnixClock::tick_in_range(10_000, 1000));
+        assert!(!UnixClock::tick_in_range(10_000, -1));
+    }
+
+    #[test]
+    fn test_error_estimate() {
+        let est_error = Duration::from_secs_f64(0.5);
+        let max_error = Duration::from_secs_f64(1.2);
+        let timex = UnixClock::error_estimate_timex(est_error, max_error);
+
+        assert_eq!(timex.modes, libc::MOD_ESTERROR | libc::MOD_MAXERROR);
+
+        // these fields are always in microseconds
+        assert_eq!(timex.esterror, 500_000);
+        assert_eq!(timex.maxerror, 1_200_000);
+    }
+
+    #[test]
+    fn test_separate_error_estimates() {
+        let timex = UnixClock::maximum_error_timex(Duration::from_millis(1200));
+        assert_eq!(timex.modes, libc::MOD_MAXERROR);
+        assert_eq!(timex.maxerror, 1_200_000);
+        assert_eq!(timex.esterror, 0);
+
+        let timex = UnixClock::estimated_error_timex(Duration::from_millis(500));
+        assert_eq!(timex.modes, libc::MOD_ESTERROR);
+        assert_eq!(timex.esterror, 500_000);
+        assert_eq!(timex.maxerror, 0);
+    }
+
+    #[test]
+    fn test_read_error_estimate() {
+        let timex = libc::timex {
+            esterror: 500_000,
+            maxerror: 1_200_000,
+            ..EMPTY_TIMEX
+        };
+
+        let (est_error, max_error) = UnixClock::error_estimate_from_timex(&timex);
+
+        assert_eq!(est_error, Duration::from_millis(500));
+        assert_eq!(max_error, Duration::from_millis(1200));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_phc_device_path() {
+        assert_eq!(phc_device_path(0), Path::new("/dev/ptp0"));
+        assert_eq!(phc_device_path(12), Path::new("/dev/ptp12"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_frequency_ratio() {
+        let sample = |system_nanos: i128, device_nanos: i128| {
+            (
+                Timestamp::from_nanos_since_epoch(system_nanos - 1_000),
+                Timestamp::from_nanos_since_epoch(device_nanos),
+                Timestamp::from_nanos_since_epoch(system_nanos + 1_000),
+            )
+        };
+
+        // the device runs 100 ppm fast
+        let first = sample(100_000_000_000, 37_000_000_000);
+        let second = sample(110_000_000_000, 47_001_000_000);
+        let ratio = frequency_ratio(first, second).unwrap();
+        assert!((ratio - 1.0001).abs() < 1e-12);
+
+        // the device runs 25 ppm slow
+        let second = sample(104_000_000_000, 40_999_900_000);
+        let ratio = frequency_ratio(first, second).unwrap();
+        assert!((ratio - 0.999975).abs() < 1e-12);
+
+        assert_eq!(frequency_ratio(first, first), None);
+        assert_eq!(frequency_ratio(second, first), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_estimate_frequency_ratio_rejects_short_intervals() {
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.estimate_frequency_ratio(Duration::from_millis(10)),
+            Err(Error::Invalid)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_index_errors() {
+        let error = UnixClock::open_index(UnixClock::MAX_PHC_INDEX + 1).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+        let error = UnixClock::open_index(UnixClock::MAX_PHC_INDEX).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().contains("/dev/ptp1048575"));
+    }
+
+    #[test]
+    fn test_clock_control_state_round_trip() {
+        let state = ClockControlState {
+            frequency: -12 << 16,
+            status: libc::STA_PLL | libc::STA_UNSYNC,
+            tai: Some(37),
+            estimated_error: 1_500,
+            maximum_error: 250_000,
+        };
+
+        let timex = state.restore_timex();
+        assert_eq!(timex.modes & libc::MOD_OFFSET, 0);
+        #[cfg(target_os = "linux")]
+        assert_eq!(timex.modes & libc::ADJ_SETOFFSET, 0);
+        assert_eq!(timex.constant, 37);
+
+        // the kernel reports the TAI offset in its own field
+        let mut read = timex;
+        read.modes = 0;
+        #[cfg(target_os = "linux")]
+        {
+            read.tai = 37;
+        }
+
+        let captured = ClockControlState::from_timex(&read);
+        #[cfg(not(target_os = "linux"))]
+        let captured = ClockControlState {
+            tai: Some(37),
+            ..captured
+        };
+        assert_eq!(captured, state);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ClockControlState>(&json).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn test_clock_control_state_skips_leap_and_read_only_bits() {
+        let state = ClockControlState {
+            frequency: 0,
+            status: libc::STA_PLL | libc::STA_INS | libc::STA_DEL | libc::STA_PPSSIGNAL,
+            tai: None,
+            estimated_error: 0,
+            maximum_error: 0,
+        };
+
+        let timex = state.restore_timex();
+        assert_ne!(timex.modes & libc::MOD_STATUS, 0);
+        assert_eq!(timex.status, libc::STA_PLL);
+    }
+
+    #[test]
+    fn test_offset_to_second_boundary() {
+        let at = |nanos| Timestamp {
+            seconds: 1_700_000_000,
+            nanos,
+        };
+        let offset = |seconds, nanos| Some(TimeOffset { seconds, nanos });
+
+        assert_eq!(UnixClock::offset_to_next_second(at(0)), None);
+        assert_eq!(UnixClock::offset_to_nearest_second(at(0)), None);
+
+        assert_eq!(
+            UnixClock::offset_to_next_second(at(1)),
+            offset(0, 999_999_999)
+        );
+        assert_eq!(
+            UnixClock::offset_to_nearest_second(at(1)),
+            offset(-1, 999_999_999)
+        );
+
+        assert_eq!(
+            UnixClock::offset_to_next_second(at(250_000_000)),
+            offset(0, 750_000_000)
+        );
+        assert_eq!(
+            UnixClock::offset_to_nearest_second(at(250_000_000)),
+            offset(-1, 750_000_000)
+        );
+
+        assert_eq!(
+            UnixClock::offset_to_next_second(at(500_000_000)),
+            offset(0, 500_000_000)
+        );
+        assert_eq!(
+            UnixClock::offset_to_nearest_second(at(500_000_000)),
+            offset(0, 500_000_000)
+        );
+
+        assert_eq!(
+            UnixClock::offset_to_next_second(at(999_999_999)),
+            offset(0, 1)
+        );
+        assert_eq!(
+            UnixClock::offset_to_nearest_second(at(999_999_999)),
+            offset(0, 1)
+        );
+    }
+
+    #[test]
+    fn test_timed_adjust() {
+        let clock = UnixClock::CLOCK_REALTIME;
+        let (now, latency) = clock.timed_adjust(|clock| clock.now()).unwrap();
+
+        assert!(now.seconds > 0);
+        assert!(latency < Duration::from_secs(1));
+
+        let clock = UnixClock::CLOCK_MONOTONIC;
+        assert_eq!(
+            clock.timed_adjust(|clock| clock.set_frequency(1.0)),
+            Err(Error::NotSupported)
+        );
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let clock = UnixClockBuilder::new().build().unwrap();
+        assert_eq!(clock.clock.clock, libc::CLOCK_REALTIME);
+        assert_eq!(
+            clock.step_threshold(),
+            UnixClockBuilder::DEFAULT_STEP_THRESHOLD
+        );
+
+        let clock = UnixClockBuilder::default()
+            .step_threshold(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        assert_eq!(clock.step_threshold(), Duration::from_millis(5));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_builder_applies_options_in_order() {
+        // open errors are surfaced
+        let result = UnixClockBuilder::new().path("/nonexistent/ptp0").build();
+        assert_eq!(result.unwrap_err(), Error::Other(libc::ENOENT));
+
+        // non-blocking mode needs a device
+        let result = UnixClockBuilder::new().nonblocking(true).build();
+        assert_eq!(result.unwrap_err(), Error::Invalid);
+
+        // /dev/null opens, and can be made non-blocking...
+        let clock = UnixClockBuilder::new()
+            .path("/dev/null")
+            .nonblocking(true)
+            .build()
+            .unwrap();
+        let flags = unsafe { libc::fcntl(clock.raw_fd().unwrap(), libc::F_GETFL) };
+        assert_ne!(flags & libc::O_NONBLOCK, 0);
+
+        // ...but is not a clock, so the kernel NTP algorithm cannot be disabled
+        let result = UnixClockBuilder::new()
+            .path("/dev/null")
+            .nonblocking(true)
+            .disable_kernel_ntp(true)
+            .build();
+        assert_eq!(result.unwrap_err(), Error::Invalid);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_wait_for_pps_times_out() {
+        use std::os::unix::io::AsFd;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        // the write end stays open, so the read end never becomes ready
+        let clock = UnixClock::from_borrowed_fd(read.as_fd());
+        assert_eq!(
+            clock.wait_for_pps(Duration::from_millis(10)),
+            Err(Error::TimedOut)
+        );
+
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.wait_for_pps(Duration::ZERO),
+            Err(Error::Invalid)
+        );
+
+        drop(write);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_poll_readable_resumes_after_signal() {
+        extern "C" fn ignore(_: libc::c_int) {}
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read, _write) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        // # Safety
+        //
+        // The handler does nothing, so it is async-signal-safe. poll is never restarted
+        // after a handler runs, even with SA_RESTART, so it fails with EINTR.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = ignore as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            assert_eq!(
+                libc::sigaction(libc::SIGUSR2, &action, std::ptr::null_mut()),
+                0
+            );
+        }
+
+        // pthread_t is a pointer on musl, which cannot be sent to another thread
+        let thread = unsafe { libc::pthread_self() } as usize;
+        let interrupter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            unsafe { libc::pthread_kill(thread as libc::pthread_t, libc::SIGUSR2) };
+        });
+
+        let start = std::time::Instant::now();
+        let timeout = Duration::from_millis(100);
+        assert_eq!(poll_readable(read.as_raw_fd(), timeout), Ok(false));
+        assert!(start.elapsed() >= timeout);
+
+        interrupter.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_drain_events() {
+        let mut queue = vec![Ok(Some(1)), Ok(Some(2)), Ok(Some(3)), Ok(None)].into_iter();
+        assert_eq!(
+            drain_events(10, || queue.next().unwrap()),
+            Ok(vec![1, 2, 3])
+        );
+
+        // stop at the limit, without reading further
+        let mut queue = vec![Ok(Some(1)), Ok(Some(2))].into_iter();
+        assert_eq!(drain_events(2, || queue.next().unwrap()), Ok(vec![1, 2]));
+        assert_eq!(drain_events::<u32>(0, || unreachable!()), Ok(vec![]));
+
+        let mut queue = vec![Ok(Some(1)), Err(Error::NoDevice)].into_iter();
+        assert_eq!(
+            drain_events(10, || queue.next().unwrap()),
+            Err(Error::NoDevice)
+        );
+
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.drain_external_timestamps(1),
+            Err(Error::Invalid)
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_set_fd_nonblocking() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let fd = file.as_raw_fd();
+        let flags = || unsafe { libc::fcntl(fd, libc::F_GETFL) };
+
+        set_fd_nonblocking(fd, true).unwrap();
+        assert_ne!(flags() & libc::O_NONBLOCK, 0);
+
+        set_fd_nonblocking(fd, false).unwrap();
+        assert_eq!(flags() & libc::O_NONBLOCK, 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_decode_device_name() {
+        let mut buffer = [0u8; 32];
+        buffer[..10].copy_from_slice(b"e1000e PTP");
+        buffer[11..16].copy_from_slice(b"\xffjunk");
+        assert_eq!(decode_device_name(&buffer).unwrap(), "e1000e PTP");
+
+        // sysfs gives the name without a null byte, but with a newline
+        assert_eq!(
+            decode_device_name(b"ice-0000:01:00.0\n").unwrap(),
+            "ice-0000:01:00.0"
+        );
+
+        assert_eq!(decode_device_name(b"\xff\xfe\0"), Err(Error::Invalid));
+        assert_eq!(UnixClock::CLOCK_REALTIME.device_name(), Err(Error::Invalid));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_scan_phc_devices() {
+        let root = std::env::temp_dir().join(format!("clock-steering-phc-{}", std::process::id()));
+        let dev = root.join("dev");
+        let sysfs = root.join("sys");
+
+        std::fs::create_dir_all(&dev).unwrap();
+        for file in ["ptp3", "ptp0", "ptpx", "ptp", "tty0"] {
+            std::fs::write(dev.join(file), "").unwrap();
+        }
+
+        std::fs::create_dir_all(sysfs.join("ptp0/device/net/eth0")).unwrap();
+        std::fs::write(sysfs.join("ptp0/clock_name"), "e1000e PTP\n").unwrap();
+        std::fs::create_dir_all(sysfs.join("ptp3")).unwrap();
+        std::fs::write(sysfs.join("ptp3/clock_name"), "KVM virtual PTP\n").unwrap();
+
+        let devices = scan_phc_devices(&dev, &sysfs);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            devices.unwrap(),
+            vec![
+                PhcDevice {
+                    path: dev.join("ptp0"),
+                    index: 0,
+                    name: Some(String::from("e1000e PTP")),
+                    capabilities: None,
+                    interface: Some(String::from("eth0")),
+                },
+                PhcDevice {
+                    path: dev.join("ptp3"),
+                    index: 3,
+                    name: Some(String::from("KVM virtual PTP")),
+                    capabilities: None,
+                    interface: None,
+                },
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timestamping_info_from_ethtool() {
+        let info = EthtoolTsInfo {
+            cmd: ETHTOOL_GET_TS_INFO,
+            so_timestamping: libc::SOF_TIMESTAMPING_TX_HARDWARE
+                | libc::SOF_TIMESTAMPING_RX_HARDWARE
+                | libc::SOF_TIMESTAMPING_RAW_HARDWARE,
+            phc_index: 2,
+            tx_types: (1 << libc::HWTSTAMP_TX_OFF) | (1 << libc::HWTSTAMP_TX_ON),
+            rx_filters: (1 << libc::HWTSTAMP_FILTER_NONE)
+                | (1 << libc::HWTSTAMP_FILTER_PTP_V2_EVENT)
+                | (1 << libc::HWTSTAMP_FILTER_NTP_ALL),
+            ..Default::default()
+        };
+
+        let info = TimestampingInfo::from_ethtool(&info);
+
+        assert!(info.flags.tx_hardware());
+        assert!(info.flags.rx_hardware());
+        assert!(info.flags.raw_hardware());
+        assert!(!info.flags.tx_software());
+        assert!(!info.flags.software());
+        assert_eq!(info.phc_index, Some(2));
+        assert_eq!(info.tx_types, vec![TxType::Off, TxType::On]);
+        assert_eq!(
+            info.rx_filters,
+            vec![RxFilter::None, RxFilter::PtpV2Event, RxFilter::NtpAll]
+        );
+
+        // a software-only interface
+        let info = EthtoolTsInfo {
+            so_timestamping: libc::SOF_TIMESTAMPING_TX_SOFTWARE
+                | libc::SOF_TIMESTAMPING_RX_SOFTWARE
+                | libc::SOF_TIMESTAMPING_SOFTWARE,
+            phc_index: -1,
+            ..Default::default()
+        };
+
+        let info = TimestampingInfo::from_ethtool(&info);
+
+        assert!(info.flags.software());
+        assert!(!info.flags.tx_hardware());
+        assert_eq!(info.phc_index, None);
+        assert!(info.tx_types.is_empty());
+        assert!(info.rx_filters.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_phc_index_for_invalid_interface() {
+        assert_eq!(phc_index_for_interface(""), Err(Error::Invalid));
+        assert_eq!(
+            phc_index_for_interface("a-very-long-interface-name"),
+            Err(Error::Invalid)
+        );
+        assert_eq!(phc_index_for_interface("eth\0"), Err(Error::Invalid));
+
+        assert_eq!(
+            phc_index_for_interface("nonexistent0"),
+            Err(Error::NoDevice)
+        );
+    }
+
+    #[test]
+    fn test_offset_magnitude() {
+        let magnitude = |seconds, nanos| UnixClock::offset_magnitude(TimeOffset { seconds, nanos });
+
+        assert_eq!(magnitude(0, 0), Duration::ZERO);
+        assert_eq!(magnitude(2, 500_000_000), Duration::from_millis(2_500));
+        assert_eq!(magnitude(-3, 0), Duration::from_secs(3));
+        assert_eq!(magnitude(-1, 750_000_000), Duration::from_millis(250));
+        assert_eq!(magnitude(-3, 500_000_000), Duration::from_millis(2_500));
+    }
+
+    #[test]
+    fn test_raw_timex_matches_set_frequency() {
+        let raw = UnixClock::raw_timex(libc::MOD_FREQUENCY, |timex| {
+            timex.freq = (12.5 * 65536.0) as libc::c_long;
+        });
+        let typed = UnixClock::set_frequency_timex(12.5);
+
+        assert_eq!(raw.modes, typed.modes);
+        assert_eq!(raw.freq, typed.freq);
+        assert_eq!(raw.offset, typed.offset);
+        assert_eq!(raw.status, typed.status);
+
+        // a failing call reports the error like the typed API does
+        let clock = UnixClock::CLOCK_MONOTONIC;
+        assert_eq!(
+            clock
+                .with_raw_timex(libc::MOD_FREQUENCY, |_| {})
+                .map(|_| ()),
+            clock.set_frequency(12.5).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_frequency_ramp() {
+        let step = Duration::from_millis(500);
+
+        // from 0 to 10 ppm at 2 ppm/s takes 5 seconds, or 10 steps
+        let mut frequency = 0.0;
+        let mut steps = 0;
+        while frequency != 10.0 {
+            frequency = FrequencyRamp::next_frequency(frequency, 10.0, 2.0, step);
+            steps += 1;
+
+            assert!(frequency <= 10.0);
+        }
+        assert_eq!(steps, 10);
+
+        // down, without overshooting the target
+        let frequency = FrequencyRamp::next_frequency(-4.5, -5.0, 2.0, step);
+        assert_eq!(frequency, -5.0);
+
+        let frequency = FrequencyRamp::next_frequency(0.0, -5.0, 2.0, step);
+        assert_eq!(frequency, -1.0);
+
+        // 0.7 + (0.1 - 0.7) is not exactly 0.1
+        let frequency = FrequencyRamp::next_frequency(0.7, 0.1, 2.0, step);
+        assert_eq!(frequency, 0.1);
+    }
+
+    #[test]
+    fn test_ramp_frequency_rate() {
+        let clock = UnixClock::CLOCK_REALTIME;
+
+        assert_eq!(clock.ramp_frequency(10.0, 0.0).unwrap_err(), Error::Invalid);
+        assert_eq!(
+            clock.ramp_frequency(10.0, -1.0).unwrap_err(),
+            Error::Invalid
+        );
+        assert_eq!(
+            clock.ramp_frequency(10.0, f64::NAN).unwrap_err(),
+            Error::Invalid
+        );
+    }
+
+    #[test]
+    fn test_slew_frequency() {
+        // 1ms over 100s is 10ppm
+        let offset = TimeOffset {
+            seconds: 0,
+            nanos: 1_000_000,
+        };
+        let frequency = UnixClock::slew_frequency(offset, Duration::from_secs(100));
+        assert!((frequency - 10.0).abs() < 1e-9);
+
+        let frequency = UnixClock::slew_frequency(offset.negate(), Duration::from_secs(100));
+        assert!((frequency + 10.0).abs() < 1e-9);
+
+        // 0.5s over 1000s is 500ppm
+        let offset = TimeOffset::from_duration_signed(0, -500_000_000);
+        let frequency = UnixClock::slew_frequency(offset, Duration::from_secs(1000));
+        assert!((frequency + 500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_slew_end() {
+        let start = Timestamp {
+            seconds: 100,
+            nanos: 900_000_000,
+        };
+
+        let offset = TimeOffset::from_duration_signed(0, -200_000_000);
+        let end = UnixClock::slew_end(start, offset, Duration::from_secs(10)).unwrap();
+
+        assert_eq!(
+            end,
+            Timestamp {
+                seconds: 110,
+                nanos: 700_000_000,
+            }
+        );
+
+        let slew = OffsetSlew {
+            applied_at: start,
+            until: end,
+            base_frequency: 0.0,
+        };
+
+        assert!(!slew.is_finished(start));
+        assert!(slew.is_finished(end));
+    }
+
+    #[test]
+    fn test_correct_offset_threshold() {
+        let threshold = Duration::from_millis(128);
+
+        let just_below = TimeOffset {
+            seconds: 0,
+            nanos: 127_999_999,
+        };
+        let at = TimeOffset {
+            seconds: 0,
+            nanos: 128_000_000,
+        };
+
+        assert!(!UnixClock::should_step(just_below, threshold));
+        assert!(!UnixClock::should_step(just_below.negate(), threshold));
+        assert!(UnixClock::should_step(at, threshold));
+        assert!(UnixClock::should_step(at.negate(), threshold));
+        assert!(!UnixClock::should_step(TimeOffset::default(), threshold));
+    }
+
+    #[test]
+    fn test_step_clock_guarded() {
+        // monotonic clocks refuse to step, which shows whether the guard let the step through
+        let clock = UnixClock::CLOCK_MONOTONIC;
+        let max_step = Duration::from_secs(1);
+
+        let at_boundary = [
+            TimeOffset {
+                seconds: 1,
+                nanos: 0,
+            },
+            TimeOffset {
+                seconds: -1,
+                nanos: 0,
+            },
+        ];
+
+        for offset in at_boundary {
+            assert_eq!(
+                clock.step_clock_guarded(offset, max_step),
+                Err(Error::NotSupported)
+            );
+        }
+
+        let over_boundary = [
+            TimeOffset {
+                seconds: 1,
+                nanos: 1,
+            },
+            TimeOffset {
+                seconds: -2,
+                nanos: 999_999_999,
+            },
+        ];
+
+        for offset in over_boundary {
+            assert_eq!(
+                clock.step_clock_guarded(offset, max_step),
+                Err(Error::Invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_leap_indicator_from_status_bits() {
+        let cases = [
+            (0, Ok(LeapIndicator::NoWarning)),
+            (libc::STA_INS, Ok(LeapIndicator::Leap61)),
+            (libc::STA_DEL, Ok(LeapIndicator::Leap59)),
+            (libc::STA_UNSYNC, Ok(LeapIndicator::Unknown)),
+            (libc::STA_UNSYNC | libc::STA_INS, Ok(LeapIndicator::Leap61)),
+            (libc::STA_UNSYNC | libc::STA_DEL, Ok(LeapIndicator::Leap59)),
+            (libc::STA_INS | libc::STA_DEL, Err(Error::Invalid)),
+        ];
+
+        for (status, expected) in cases {
+            // other flags do not matter
+            let status = status | libc::STA_PLL | libc::STA_NANO;
+            assert_eq!(LeapIndicator::from_status_bits(status), expected);
+        }
+
+        // round trip with the bits that set_leap_seconds writes
+        for leap in [
+            LeapIndicator::NoWarning,
+            LeapIndicator::Leap61,
+            LeapIndicator::Leap59,
+            LeapIndicator::Unknown,
+        ] {
+            assert_eq!(
+                LeapIndicator::from_status_bits(leap.as_status_bit()),
+                Ok(leap)
+            );
+        }
+    }
+
+    #[test]
+    fn test_synchronized_status() {
+        for leap in [0, libc::STA_INS, libc::STA_DEL] {
+            let status = libc::STA_PLL | leap;
+
+            let unsynced = UnixClock::synchronized_status(status, false);
+            assert_eq!(unsynced, status | libc::STA_UNSYNC);
+
+            let synced = UnixClock::synchronized_status(unsynced, true);
+            assert_eq!(synced, status);
+
+            // setting the current state again changes nothing
+            assert_eq!(UnixClock::synchronized_status(synced, true), status);
+        }
+    }
+
+    #[test]
+    fn test_pps_discipline_status() {
+        let other = libc::STA_PLL | libc::STA_NANO;
+        let both = libc::STA_PPSTIME | libc::STA_PPSFREQ;
+
+        for status in [other, other | both] {
+            assert_eq!(
+                UnixClock::pps_discipline_status(status, false, false),
+                other
+            );
+            assert_eq!(
+                UnixClock::pps_discipline_status(status, true, false),
+                other | libc::STA_PPSTIME
+            );
+            assert_eq!(
+                UnixClock::pps_discipline_status(status, false, true),
+                other | libc::STA_PPSFREQ
+            );
+            assert_eq!(
+                UnixClock::pps_discipline_status(status, true, true),
+                other | both
+            );
+        }
+    }
+
+    #[test]
+    fn test_snapshot_from_timex() {
+        let timex = libc::timex {
+            freq: 25 << 16,
+            maxerror: 1_500,
+            esterror: 250,
+            status: libc::STA_PLL | libc::STA_INS,
+            #[cfg(target_os = "linux")]
+            tai: 37,
+            ..EMPTY_TIMEX
+        };
+
+        let time = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_789,
+        };
+
+        let snapshot = ClockSnapshot::from_timex(&timex, time, false);
+
+        assert_eq!(snapshot.time, time);
+        assert_eq!(snapshot.frequency, 25.0);
+        assert_eq!(snapshot.estimated_error, Some(Duration::from_micros(250)));
+        assert_eq!(snapshot.maximum_error, Some(Duration::from_micros(1_500)));
+        assert!(snapshot.status.pll());
+        assert!(snapshot.status.insert_leap());
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(snapshot.tai, Some(37));
+        #[cfg(not(target_os = "linux"))]
+        assert_eq!(snapshot.tai, None);
+
+        // hardware clocks do not track error estimates
+        let snapshot = ClockSnapshot::from_timex(&timex, time, true);
+
+        assert_eq!(snapshot.estimated_error, None);
+        assert_eq!(snapshot.maximum_error, None);
     }
 
-    fn set_leap_seconds(&self, leap_status: LeapIndicator) -> Result<(), Self::Error> {
-        self.update_status(|status| {
-            (status & !(libc::STA_UNSYNC | libc::STA_INS | libc::STA_DEL))
-                | leap_status.as_status_bit()
-        })
+    #[test]
+    fn test_precision_from_timex() {
+        let timex = libc::timex {
+            precision: 1,
+            ..EMPTY_TIMEX
+        };
+        assert_eq!(
+            UnixClock::precision_from_timex(&timex),
+            Some(Duration::from_micros(1))
+        );
+
+        let timex = libc::timex {
+            precision: 250,
+            ..EMPTY_TIMEX
+        };
+        assert_eq!(
+            UnixClock::precision_from_timex(&timex),
+            Some(Duration::from_micros(250))
+        );
+
+        assert_eq!(UnixClock::precision_from_timex(&EMPTY_TIMEX), None);
     }
 
-    fn error_estimate_update(
-        &self,
-        est_error: Duration,
-        max_error: Duration,
-    ) -> Result<(), Self::Error> {
-        let mut timex = Self::error_estimate_timex(est_error, max_error);
-        Error::ignore_not_supported(self.adjtime(&mut timex))
+    #[test]
+    fn test_pps_status_from_timex() {
+        let timex = libc::timex {
+            status: libc::STA_PPSSIGNAL | libc::STA_PPSWANDER | libc::STA_NANO,
+            jitter: 1_500,
+            stabil: 3 << 16,
+            jitcnt: 4,
+            calcnt: 12,
+            ..EMPTY_TIMEX
+        };
+
+        assert_eq!(
+            PpsStatus::from_timex(&timex),
+            PpsStatus {
+                signal: true,
+                jitter_exceeded: false,
+                wander_exceeded: true,
+                error: false,
+                jitter: Duration::from_nanos(1_500),
+                stability: 3.0,
+                jitter_count: 4,
+                calibration_count: 12,
+            }
+        );
+
+        // without STA_NANO the jitter is in microseconds
+        let timex = libc::timex {
+            status: libc::STA_PPSJITTER | libc::STA_PPSERROR,
+            jitter: 1_500,
+            ..EMPTY_TIMEX
+        };
+
+        let status = PpsStatus::from_timex(&timex);
+        assert!(!status.signal);
+        assert!(status.jitter_exceeded);
+        assert!(status.error);
+        assert_eq!(status.jitter, Duration::from_micros(1_500));
     }
 
-    fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
-        let mut timex = EMPTY_TIMEX;
-        self.adjtime(&mut timex)?;
+    #[test]
+    fn test_jitter_and_stability_units() {
+        let mut timex = libc::timex {
+            status: libc::STA_NANO,
+            jitter: 2_500,
+            stabil: 3 << 15,
+            ..EMPTY_TIMEX
+        };
 
-        // We are setting the status bits
-        timex.modes = libc::MOD_STATUS;
+        assert_eq!(
+            UnixClock::jitter_from_timex(&timex),
+            Duration::from_nanos(2_500)
+        );
+        assert_eq!(UnixClock::stability_from_timex(&timex), 1.5);
 
-        // Disable all kernel time control loops (phase lock, frequency lock, pps time and pps frequency).
-        timex.status &= !(libc::STA_PLL | libc::STA_FLL | libc::STA_PPSTIME | libc::STA_PPSFREQ);
+        timex.status = 0;
+        assert_eq!(
+            UnixClock::jitter_from_timex(&timex),
+            Duration::from_micros(2_500)
+        );
+        assert_eq!(UnixClock::stability_from_timex(&timex), 1.5);
 
-        // ignore if we cannot disable the kernel time control loops (e.g. external clocks)
-        Error::ignore_not_supported(self.adjtime(&mut timex))
+        // a negative jitter is nonsense, and treated as zero
+        timex.jitter = -1;
+        assert_eq!(UnixClock::jitter_from_timex(&timex), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_supported_operations() {
+        let realtime = UnixClock::CLOCK_REALTIME.supported_operations();
+        assert!(realtime.read && realtime.frequency && realtime.step);
+        assert!(realtime.leap_seconds && realtime.error_estimate && realtime.kernel_discipline);
+        assert_eq!(realtime.tai, UnixClock::supports_tai());
+
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.supported_operations(),
+            SupportedOps::READ_ONLY
+        );
+
+        #[cfg(target_os = "linux")]
+        {
+            for clock in [
+                UnixClock::CLOCK_TAI,
+                UnixClock::CLOCK_MONOTONIC_RAW,
+                UnixClock::CLOCK_BOOTTIME,
+            ] {
+                assert_eq!(clock.supported_operations(), SupportedOps::READ_ONLY);
+            }
+
+            let device = UnixClock::open("/dev/null").unwrap();
+            let ops = device.supported_operations();
+            assert!(ops.read && ops.frequency && ops.step);
+            assert!(!ops.leap_seconds && !ops.tai && !ops.error_estimate);
+            assert!(!ops.kernel_discipline);
+        }
+    }
+
+    #[test]
+    fn test_supports_tai() {
+        #[cfg(target_os = "linux")]
+        assert!(UnixClock::supports_tai());
+        #[cfg(target_os = "freebsd")]
+        assert!(UnixClock::supports_tai());
+        #[cfg(target_os = "macos")]
+        assert!(!UnixClock::supports_tai());
+
+        if !UnixClock::supports_tai() {
+            assert_eq!(
+                UnixClock::CLOCK_REALTIME.get_tai(),
+                Err(Error::NotSupported)
+            );
+        }
     }
 
     #[cfg(target_os = "linux")]
-    fn set_tai(&self, tai_offset: i32) -> Result<(), Error> {
+    #[test]
+    fn test_nanosecond_mode_decoding() {
         let mut timex = libc::timex {
-            modes: libc::ADJ_TAI,
-            constant: tai_offset as _,
+            time: libc::timeval {
+                tv_sec: 1,
+                tv_usec: 500,
+            },
             ..EMPTY_TIMEX
         };
 
-        self.clock_adjtime(&mut timex)
+        let clock = UnixClock::CLOCK_REALTIME;
+        assert_eq!(
+            clock.extract_current_time(&timex).unwrap(),
+            Timestamp {
+                seconds: 1,
+                nanos: 500_000,
+            }
+        );
+
+        timex.status = libc::STA_NANO;
+        assert!(ClockStatus::from_bits(timex.status).nanosecond());
+        assert_eq!(
+            clock.extract_current_time(&timex).unwrap(),
+            Timestamp {
+                seconds: 1,
+                nanos: 500,
+            }
+        );
     }
 
-    #[cfg(not(target_os = "linux"))]
-    fn set_tai(&self, _tai_offset: i32) -> Result<(), Error> {
-        Err(Error::NotSupported)
+    #[test]
+    fn test_clock_status() {
+        let status = ClockStatus::from_bits(libc::STA_PLL | libc::STA_UNSYNC);
+        assert!(status.pll());
+        assert!(status.unsynchronized());
+        assert!(!status.fll());
+        assert!(!status.pps_signal());
+
+        let status =
+            ClockStatus::from_bits(libc::STA_PPSSIGNAL | libc::STA_PPSTIME | libc::STA_NANO);
+        assert!(status.pps_signal());
+        assert!(status.pps_time());
+        assert!(status.nanosecond());
+        assert!(!status.pps_frequency());
+        assert!(!status.unsynchronized());
+
+        let status = ClockStatus::from_bits(0);
+        assert_eq!(status, ClockStatus::default());
+        assert!(!status.insert_leap());
+        assert!(!status.delete_leap());
     }
 
-    #[cfg(target_os = "linux")]
-    fn get_tai(&self) -> Result<i32, Error> {
-        let mut timex = EMPTY_TIMEX;
-        if self.clock_adjtime(&mut timex).is_ok() {
-            Ok(timex.tai)
-        } else {
-            // hardware clock which doesn't have an offset anyway
-            Ok(0)
+    #[test]
+    fn test_clock_state_from_raw() {
+        let states = [
+            (TIME_OK, ClockState::Ok),
+            (TIME_INS, ClockState::InsertPending),
+            (TIME_DEL, ClockState::DeletePending),
+            (TIME_OOP, ClockState::InProgress),
+            (TIME_WAIT, ClockState::LeapOccurred),
+            (TIME_ERROR, ClockState::Unsynchronized),
+        ];
+
+        for (raw, state) in states {
+            assert_eq!(ClockState::from_raw(raw), state, "{raw}");
         }
+
+        assert!(ClockState::InsertPending.leap_pending());
+        assert!(ClockState::DeletePending.leap_pending());
+        assert!(ClockState::InProgress.leap_pending());
+        assert!(!ClockState::LeapOccurred.leap_pending());
+        assert!(!ClockState::Ok.leap_pending());
     }
 
-    #[cfg(not(target_os = "linux"))]
-    fn get_tai(&self) -> Result<i32, Error> {
-        Err(Error::NotSupported)
+    #[test]
+    fn test_clock_state() {
+        // the kernel reports a state from the documented set, whichever it is right now
+        UnixClock::CLOCK_REALTIME.clock_state().unwrap();
+
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.clock_state(),
+            Err(Error::NotSupported)
+        );
+
+        // unknown states are not trusted
+        assert_eq!(ClockState::from_raw(6), ClockState::Unsynchronized);
+        assert_eq!(ClockState::from_raw(i32::MAX), ClockState::Unsynchronized);
     }
-}
 
-/// Errors that can be thrown by modifying a unix clock
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum Error {
-    /// Insufficient permissions to interact with the clock.
-    NoPermission,
-    /// No access to the clock.
-    NoAccess,
-    /// Invalid operation requested
-    Invalid,
-    /// Clock device has gone away
-    NoDevice,
-    /// Clock operation requested is not supported by operating system.
-    NotSupported,
-}
+    #[test]
+    fn test_scheduled_leap_decode() {
+        let decode = ScheduledLeap::decode;
 
-impl core::fmt::Display for Error {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        use Error::*;
+        assert_eq!(
+            decode(libc::STA_INS, ClockState::InsertPending),
+            Ok(Some((LeapIndicator::Leap61, false)))
+        );
+        assert_eq!(
+            decode(libc::STA_DEL, ClockState::DeletePending),
+            Ok(Some((LeapIndicator::Leap59, false)))
+        );
+        assert_eq!(
+            decode(libc::STA_INS, ClockState::InProgress),
+            Ok(Some((LeapIndicator::Leap61, true)))
+        );
 
-        let msg = match self {
-            NoPermission => "Insufficient permissions to interact with the clock.",
-            NoAccess => "No access to the clock.",
-            Invalid => "Invalid operation requested",
-            NoDevice => "Clock device has gone away",
-            NotSupported => "Clock operation requested is not supported by operating system.",
-        };
+        // not yet picked up by the kernel
+        assert_eq!(
+            decode(libc::STA_DEL, ClockState::Ok),
+            Ok(Some((LeapIndicator::Leap59, false)))
+        );
+        assert_eq!(
+            decode(libc::STA_INS | libc::STA_UNSYNC, ClockState::Unsynchronized),
+            Ok(Some((LeapIndicator::Leap61, false)))
+        );
 
-        f.write_str(msg)
+        // already applied
+        assert_eq!(decode(libc::STA_INS, ClockState::LeapOccurred), Ok(None));
+
+        // nothing scheduled
+        assert_eq!(decode(0, ClockState::Ok), Ok(None));
+        assert_eq!(
+            decode(libc::STA_UNSYNC, ClockState::Unsynchronized),
+            Ok(None)
+        );
+
+        assert_eq!(
+            decode(libc::STA_INS | libc::STA_DEL, ClockState::Ok),
+            Err(Error::Invalid)
+        );
     }
-}
 
-impl std::error::Error for Error {}
+    #[test]
+    fn test_leap_smear_integrates_to_one_second() {
+        // 2016-12-31T12:00:00Z, half a day before the last leap second so far
+        let start = 1_483_185_600;
+        let smear = |direction, window| LeapSmear {
+            clock: &UnixClock::CLOCK_REALTIME,
+            direction,
+            window,
+            at: Timestamp {
+                seconds: start + 43200,
+                nanos: 0,
+            },
+            base_frequency: 0.0,
+        };
 
-impl Error {
-    /// Turn the `Error::NotSupported` error variant into `Ok(())`, to silently
-    /// ignore operations that are not supported by the current clock. All
-    /// other input values are untouched.
-    pub fn ignore_not_supported(res: Result<(), Error>) -> Result<(), Error> {
-        match res {
-            Err(Error::NotSupported) => Ok(()),
-            other => other,
+        // integrate the offset over a day, one second at a time
+        let integrate = |smear: &LeapSmear, from| -> f64 {
+            (from..from + 86400)
+                .map(|seconds| smear.frequency_offset(Timestamp { seconds, nanos: 0 }) * 1e-6)
+                .sum()
+        };
+
+        for (direction, expected) in [(LeapIndicator::Leap61, -1.0), (LeapIndicator::Leap59, 1.0)] {
+            for window in [Duration::from_secs(86400), Duration::from_secs(7200)] {
+                let smear = smear(direction, window);
+
+                let offset = integrate(&smear, start);
+                assert!((offset - expected).abs() < 1e-9, "{offset}");
+
+                // the following day, and its midnight, are not smeared
+                assert_eq!(integrate(&smear, start + 86400), 0.0);
+                assert_eq!(integrate(&smear, start + 86400 + 43200), 0.0);
+            }
         }
+
+        // 12 hours before midnight is within a day-long window, but not within a shorter one
+        let noon = Timestamp {
+            seconds: start,
+            nanos: 0,
+        };
+        let day = smear(LeapIndicator::Leap61, Duration::from_secs(86400));
+        assert!((day.frequency_offset(noon) + 1e6 / 86400.0).abs() < 1e-9);
+
+        let hours = smear(LeapIndicator::Leap61, Duration::from_secs(7200));
+        assert_eq!(hours.frequency_offset(noon), 0.0);
+
+        let none = smear(LeapIndicator::NoWarning, Duration::from_secs(7200));
+        assert_eq!(none.frequency_offset(noon), 0.0);
     }
 
-    // TODO: use https://doc.rust-lang.org/std/io/type.RawOsError.html when stable
-    fn into_raw_os_error(self) -> i32 {
-        match self {
-            Self::NoPermission => libc::EPERM,
-            Self::NoAccess => libc::EACCES,
-            Self::Invalid => libc::EINVAL,
-            Self::NoDevice => libc::ENODEV,
-            Self::NotSupported => libc::EOPNOTSUPP,
+    #[test]
+    fn test_leap_smear_rejects_clamped_frequencies() {
+        let clock = UnixClock::CLOCK_REALTIME;
+
+        // the smear would need 1000 ppm
+        assert_eq!(
+            clock
+                .smear_leap(LeapIndicator::Leap61, Duration::from_secs(1000))
+                .unwrap_err(),
+            Error::Invalid
+        );
+        assert_eq!(
+            clock
+                .smear_leap(LeapIndicator::Leap61, Duration::ZERO)
+                .unwrap_err(),
+            Error::Invalid
+        );
+
+        let smear = clock
+            .smear_leap(LeapIndicator::Leap61, Duration::from_secs(86400))
+            .unwrap();
+        assert!(smear.at() > clock.now().unwrap());
+        assert_eq!(smear.at().seconds % 86400, 0);
+
+        let smear = smear.with_base_frequency(600.0);
+        assert_eq!(smear.update(smear.at()), Err(Error::Invalid));
+    }
+
+    #[test]
+    fn test_scheduled_leap_end_of_day() {
+        // 2016-12-31T12:00:00Z, the day of the last leap second so far
+        let noon = Timestamp {
+            seconds: 1_483_185_600,
+            nanos: 500,
+        };
+
+        assert_eq!(
+            ScheduledLeap::end_of_day(noon),
+            Timestamp {
+                seconds: 1_483_228_800,
+                nanos: 0,
+            }
+        );
+
+        // the last second of the day
+        let last = Timestamp {
+            seconds: 1_483_228_799,
+            nanos: 999_999_999,
+        };
+        assert_eq!(
+            ScheduledLeap::end_of_day(last),
+            ScheduledLeap::end_of_day(noon)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_has_cap_sys_time() {
+        // root, with all capabilities
+        let status = "Name:\tntp-daemon\nCapInh:\t0000000000000000\nCapPrm:\t000001ffffffffff\nCapEff:\t000001ffffffffff\n";
+        assert_eq!(has_cap_sys_time(status), Some(true));
+
+        // only CAP_SYS_TIME, e.g. granted by systemd's AmbientCapabilities
+        let status = "CapPrm:\t0000000002000000\nCapEff:\t0000000002000000\n";
+        assert_eq!(has_cap_sys_time(status), Some(true));
+
+        // permitted, but not effective
+        let status = "CapPrm:\t0000000002000000\nCapEff:\t0000000000000000\n";
+        assert_eq!(has_cap_sys_time(status), Some(false));
+
+        // CAP_NET_ADMIN and CAP_NET_RAW only
+        let status = "CapEff:\t0000000000003000\n";
+        assert_eq!(has_cap_sys_time(status), Some(false));
+
+        assert_eq!(has_cap_sys_time("Name:\tcat\n"), None);
+        assert_eq!(has_cap_sys_time("CapEff:\tgarbage\n"), None);
+    }
+
+    #[test]
+    fn test_can_steer() {
+        assert!(!UnixClock::CLOCK_MONOTONIC.can_steer());
+
+        // devices are steerable when opened for writing
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsFd;
+
+            let file = std::fs::File::open("/dev/null").unwrap();
+            assert!(!UnixClock::from_borrowed_fd(file.as_fd()).can_steer());
+
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .open("/dev/null")
+                .unwrap();
+            assert!(UnixClock::from_borrowed_fd(file.as_fd()).can_steer());
         }
     }
-}
 
-impl From<Error> for std::io::Error {
-    fn from(value: Error) -> Self {
-        std::io::Error::from_raw_os_error(value.into_raw_os_error())
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tai_info() {
+        let info = UnixClock::CLOCK_REALTIME.tai_info().unwrap();
+        assert_eq!(info.tai, UnixClock::CLOCK_REALTIME.get_tai().unwrap());
+
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.tai_info().unwrap_err(),
+            Error::NotSupported
+        );
     }
-}
 
-fn error_number() -> libc::c_int {
     #[cfg(target_os = "linux")]
-    unsafe {
-        *libc::__errno_location()
+    #[test]
+    fn test_capabilities_from_ptp_clock_caps() {
+        let mut caps: libc::ptp_clock_caps = unsafe { std::mem::zeroed() };
+        caps.max_adj = 62_499_999;
+        caps.n_alarm = 1;
+        caps.n_ext_ts = 2;
+        caps.n_per_out = 3;
+        caps.n_pins = 4;
+        caps.pps = 1;
+        caps.cross_timestamping = 0;
+
+        let capabilities = Capabilities::from_ptp_clock_caps(&caps);
+
+        assert_eq!(
+            capabilities,
+            Capabilities {
+                max_adj: 62_499_999,
+                n_alarm: 1,
+                n_ext_ts: 2,
+                n_per_out: 3,
+                n_pins: 4,
+                pps: true,
+                cross_timestamping: false,
+            }
+        );
+        assert_eq!(capabilities.max_frequency_ppm(), 62_499.999);
     }
 
-    #[cfg(not(target_os = "linux"))]
-    unsafe {
-        *libc::__error()
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ext_ts_flags() {
+        assert_eq!(ExtTsFlags::Disabled.as_raw(), 0);
+        assert_eq!(ExtTsFlags::RisingEdge.as_raw(), 0b1011);
+        assert_eq!(ExtTsFlags::FallingEdge.as_raw(), 0b1101);
+        assert_eq!(ExtTsFlags::BothEdges.as_raw(), 0b1111);
     }
-}
 
-// Convert those error numbers that can occur for calls to the following
-// functions
-// - ntp_adjtimex https://man7.org/linux/man-pages/man3/ntp_adjtime.3.html
-// - clock_gettime & clock_settime https://man7.org/linux/man-pages/man3/clock_gettime.3.html
-fn convert_errno() -> Error {
-    match error_number() {
-        libc::EINVAL => Error::Invalid,
-        // The documentation is a bit unclear if this can happen with
-        // non-dynamic clocks like the ntp kapi clock, however deal with it just in case.
-        libc::ENODEV => Error::NoDevice,
-        libc::EOPNOTSUPP => Error::NotSupported,
-        libc::EPERM => Error::NoPermission,
-        libc::EACCES => Error::NoAccess,
-        libc::EFAULT => unreachable!("we always pass in valid (accessible) buffers"),
-        // No other errors should occur
-        other => {
-            let error = std::io::Error::from_raw_os_error(other);
-            unreachable!("error code `{other}` ({error:?}) should not occur")
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ext_ts_event() {
+        let raw = PtpExtTsEventRaw {
+            t: libc::ptp_clock_time {
+                sec: 1_700_000_000,
+                nsec: 123_456_789,
+                reserved: 0,
+            },
+            index: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(std::mem::size_of::<PtpExtTsEventRaw>(), 32);
+        assert_eq!(
+            PtpExtTsEvent::from_raw(&raw),
+            PtpExtTsEvent {
+                channel: 2,
+                timestamp: Timestamp {
+                    seconds: 1_700_000_000,
+                    nanos: 123_456_789,
+                },
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_perout_request() {
+        let start = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 250_000_000,
+        };
+        let period = Duration::from_millis(1500);
+
+        let request = UnixClock::perout_request(3, start, period, PerOutFlags::default());
+
+        assert_eq!(request.index, 3);
+        assert_eq!(request.flags, 0);
+        assert_eq!((request.period.sec, request.period.nsec), (1, 500_000_000));
+        let start = unsafe { request.anonymous_1.start };
+        assert_eq!((start.sec, start.nsec), (1_700_000_000, 250_000_000));
+        assert_eq!(unsafe { request.anonymous_2.rsv }, [0; 4]);
+
+        let flags = PerOutFlags {
+            duty_cycle: Some(Duration::from_millis(100)),
+            phase: true,
+        };
+        let request = UnixClock::perout_request(0, Timestamp::default(), period, flags);
+
+        assert_eq!(request.flags, PTP_PEROUT_DUTY_CYCLE | PTP_PEROUT_PHASE);
+        let on = unsafe { request.anonymous_2.on };
+        assert_eq!((on.sec, on.nsec), (0, 100_000_000));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pin_function_raw() {
+        let functions = [
+            PinFunction::None,
+            PinFunction::ExternalTimestamp,
+            PinFunction::PeriodicOutput,
+            PinFunction::PhysicalSync,
+        ];
+
+        for (raw, function) in functions.into_iter().enumerate() {
+            assert_eq!(function.as_raw(), raw as libc::c_uint);
+            assert_eq!(PinFunction::from_raw(raw as libc::c_uint), Some(function));
         }
+
+        assert_eq!(PinFunction::from_raw(4), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_clamp_extended_samples() {
+        assert_eq!(UnixClock::clamp_extended_samples(0), 1);
+        assert_eq!(UnixClock::clamp_extended_samples(10), 10);
+        assert_eq!(UnixClock::clamp_extended_samples(25), 25);
+        assert_eq!(UnixClock::clamp_extended_samples(100), 25);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_extended_samples() {
+        let mut offset: libc::ptp_sys_offset_extended = unsafe { std::mem::zeroed() };
+        offset.n_samples = 2;
+        offset.ts[0] = [
+            ptp_clock_time(100, 10),
+            ptp_clock_time(137, 20),
+            ptp_clock_time(100, 30),
+        ];
+        offset.ts[1] = [
+            ptp_clock_time(101, 40),
+            ptp_clock_time(138, 50),
+            ptp_clock_time(101, 60),
+        ];
+        offset.ts[2] = [ptp_clock_time(999, 0); 3];
+
+        let samples = UnixClock::extended_samples(&offset, 37);
+
+        let timestamp = |seconds, nanos| Timestamp { seconds, nanos };
+        assert_eq!(
+            samples,
+            vec![
+                (timestamp(137, 10), timestamp(137, 20), timestamp(137, 30)),
+                (timestamp(138, 40), timestamp(138, 50), timestamp(138, 60)),
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_system_midpoint() {
+        let before = Timestamp {
+            seconds: 137,
+            nanos: 999_999_900,
+        };
+        let after = Timestamp {
+            seconds: 138,
+            nanos: 100,
+        };
+
+        let timestamp = |seconds| Timestamp { seconds, nanos: 0 };
+
+        // TAI, and UTC 37 seconds behind it
+        let midpoint = UnixClock::system_midpoint(before, after, 0);
+        assert_eq!(midpoint, timestamp(138));
+        let midpoint = UnixClock::system_midpoint(before, after, 37);
+        assert_eq!(midpoint, timestamp(101));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_measure_system_offset_without_device() {
+        // neither clock is a device, so both are read like `measure_offset` does
+        let (target_time, offset) =
+            UnixClock::measure_system_offset(&UnixClock::CLOCK_REALTIME, &UnixClock::CLOCK_TAI)
+                .unwrap();
+
+        assert!(target_time > Timestamp::default());
+        assert!(offset.duration < Duration::from_secs(1000));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timestamp_from_ptp_clock_time() {
+        let offset = libc::ptp_sys_offset_precise {
+            device: ptp_clock_time(1_700_000_037, 5),
+            sys_realtime: ptp_clock_time(1_700_000_000, 999_999_999),
+            sys_monoraw: ptp_clock_time(12_345, 0),
+            rsv: [0; 4],
+        };
+
+        assert_eq!(
+            timestamp_from_ptp_clock_time(&offset.device),
+            Timestamp {
+                seconds: 1_700_000_037,
+                nanos: 5,
+            }
+        );
+        assert_eq!(
+            timestamp_from_ptp_clock_time(&offset.sys_realtime),
+            Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 999_999_999,
+            }
+        );
+        assert_eq!(
+            timestamp_from_ptp_clock_time(&offset.sys_monoraw),
+            Timestamp {
+                seconds: 12_345,
+                nanos: 0,
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_external_timestamp_requires_device() {
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.enable_external_timestamp(0, ExtTsFlags::RisingEdge),
+            Err(Error::Invalid)
+        );
+        assert_eq!(
+            UnixClock::CLOCK_REALTIME.read_external_timestamp(),
+            Err(Error::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_realtime_capabilities() {
+        let capabilities = UnixClock::CLOCK_REALTIME.capabilities().unwrap();
+
+        assert_eq!(capabilities.max_frequency_ppm(), 500.0);
+        assert_eq!(capabilities.n_pins, 0);
     }
-}
 
-fn cerr(c_int: libc::c_int) -> Result<(), Error> {
-    if c_int == -1 {
-        Err(convert_errno())
-    } else {
-        Ok(())
+    #[test]
+    fn test_monotonic_cannot_be_steered() {
+        let clock = UnixClock::CLOCK_MONOTONIC;
+
+        assert_ne!(clock.now().unwrap(), Timestamp::default());
+        assert_ne!(clock.resolution().unwrap(), Timestamp::default());
+
+        assert_eq!(clock.set_frequency(1.0), Err(Error::NotSupported));
+        assert_eq!(clock.get_frequency(), Err(Error::NotSupported));
+        assert_eq!(
+            clock.step_clock(TimeOffset::default()),
+            Err(Error::NotSupported)
+        );
+        assert_eq!(
+            clock.set_leap_seconds(LeapIndicator::NoWarning),
+            Err(Error::NotSupported)
+        );
+
+        // no TAI offset either, however it is read
+        assert_eq!(clock.get_tai(), Err(Error::NotSupported));
+        assert_eq!(clock.set_tai(37), Err(Error::NotSupported));
+        assert_eq!(clock.tai_info().unwrap_err(), Error::NotSupported);
     }
-}
 
-pub(crate) enum Precision {
-    Nano,
-    #[cfg_attr(any(target_os = "freebsd", target_os = "macos"), allow(unused))]
-    Micro,
-}
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monotonic_raw_cannot_be_steered() {
+        let clock = UnixClock::CLOCK_MONOTONIC_RAW;
 
-#[cfg_attr(target_os = "linux", allow(unused))]
-fn current_time_timespec(timespec: libc::timespec, precision: Precision) -> Timestamp {
-    let mut seconds = timespec.tv_sec;
+        assert_ne!(clock.now().unwrap(), Timestamp::default());
+        assert_eq!(clock.set_frequency(1.0), Err(Error::NotSupported));
+    }
 
-    let nanos: i32 = timespec.tv_nsec as _;
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_boottime_cannot_be_steered() {
+        let clock = UnixClock::CLOCK_BOOTTIME;
 
-    let mut nanos = match precision {
-        Precision::Nano => nanos,
-        Precision::Micro => nanos.checked_mul(1000).unwrap_or_default(),
-    };
+        assert_ne!(clock.now().unwrap(), Timestamp::default());
+        assert_ne!(clock.resolution().unwrap(), Timestamp::default());
+        assert_eq!(
+            clock.step_clock(TimeOffset::default()),
+            Err(Error::NotSupported)
+        );
+    }
 
-    // on macOS (at least) we've observed higher nanosecond counts than appear valid
-    while nanos > 1_000_000_000 {
-        seconds = seconds.wrapping_add(1);
-        nanos -= 1_000_000_000;
+    #[test]
+    fn test_now_sampled() {
+        let clock = UnixClock::CLOCK_REALTIME;
+
+        assert_eq!(clock.now_sampled(0), Err(Error::Invalid));
+
+        let sampled = clock.now_sampled(5).unwrap();
+        assert_ne!(sampled.timestamp(), Timestamp::default());
+        assert!(sampled.uncertainty() < Duration::from_secs(1));
     }
 
-    // we disallow negative nanoseconds
-    while nanos < 0 {
-        seconds = seconds.wrapping_sub(1);
-        nanos += 1_000_000_000;
+    #[test]
+    fn test_no_device() {
+        assert_eq!(error_from_errno(libc::ENODEV), Error::NoDevice);
+        assert!(UnixClock::CLOCK_REALTIME.is_alive());
     }
 
-    Timestamp {
-        seconds,
-        nanos: nanos as u32,
+    #[test]
+    fn test_unexpected_errno() {
+        let error = error_from_errno(libc::ENOSPC);
+        assert_eq!(error, Error::Other(libc::ENOSPC));
+        assert_eq!(error.into_raw_os_error(), libc::ENOSPC);
+
+        let io_error = std::io::Error::from(error);
+        assert_eq!(io_error.raw_os_error(), Some(libc::ENOSPC));
     }
-}
 
-#[cfg_attr(not(target_os = "linux"), allow(unused))]
-fn current_time_timeval(timespec: libc::timeval, precision: Precision) -> Timestamp {
-    let seconds = timespec.tv_sec;
-    let nanos = match precision {
-        Precision::Nano => timespec.tv_usec as u32,
-        Precision::Micro => (timespec.tv_usec as u32)
-            .checked_mul(1000)
-            .unwrap_or_default(),
-    };
+    #[test]
+    fn test_io_error_round_trip() {
+        let errors = [
+            Error::NoPermission,
+            Error::NoAccess,
+            Error::Invalid,
+            Error::NoDevice,
+            Error::NotSupported,
+            Error::OperationUnsupported,
+            Error::TimedOut,
+            Error::Other(libc::ENOSPC),
+        ];
 
-    Timestamp { seconds, nanos }
-}
+        for error in errors {
+            let io_error = std::io::Error::from(error);
+            assert_eq!(Error::try_from(&io_error).unwrap(), error);
+        }
 
-const EMPTY_TIMESPEC: libc::timespec = libc::timespec {
-    tv_sec: 0,
-    tv_nsec: 0,
-};
+        let io_error = std::io::Error::from(Error::OperationUnsupported);
+        assert_eq!(io_error.raw_os_error(), Some(libc::EOPNOTSUPP));
 
-// Libc has no good other way of obtaining this, so let's at least make our
-// functions more readable.
-#[cfg(all(target_os = "linux", target_env = "gnu"))]
-pub const EMPTY_TIMEX: libc::timex = libc::timex {
-    modes: 0,
-    offset: 0,
-    freq: 0,
-    maxerror: 0,
-    esterror: 0,
-    status: 0,
-    constant: 0,
-    precision: 0,
-    tolerance: 0,
-    time: libc::timeval {
-        tv_sec: 0,
-        tv_usec: 0,
-    },
-    tick: 0,
-    ppsfreq: 0,
-    jitter: 0,
-    shift: 0,
-    stabil: 0,
-    jitcnt: 0,
-    calcnt: 0,
-    errcnt: 0,
-    stbcnt: 0,
-    tai: 0,
-    __unused1: 0,
-    __unused2: 0,
-    __unused3: 0,
-    __unused4: 0,
-    __unused5: 0,
-    __unused6: 0,
-    __unused7: 0,
-    __unused8: 0,
-    __unused9: 0,
-    __unused10: 0,
-    __unused11: 0,
-};
+        // the OS has no code for a step mismatch, so it is reported as an I/O error
+        let io_error = std::io::Error::from(Error::StepMismatch);
+        assert_eq!(Error::try_from(&io_error).unwrap(), Error::Other(libc::EIO));
 
-#[cfg(all(target_os = "linux", target_env = "musl"))]
-pub const EMPTY_TIMEX: libc::timex = libc::timex {
-    modes: 0,
-    offset: 0,
-    freq: 0,
-    maxerror: 0,
-    esterror: 0,
-    status: 0,
-    constant: 0,
-    precision: 0,
-    tolerance: 0,
-    time: libc::timeval {
-        tv_sec: 0,
-        tv_usec: 0,
-    },
-    tick: 0,
-    ppsfreq: 0,
-    jitter: 0,
-    shift: 0,
-    stabil: 0,
-    jitcnt: 0,
-    calcnt: 0,
-    errcnt: 0,
-    stbcnt: 0,
-    tai: 0,
-    __padding: [0; 11],
-};
+        let io_error = std::io::Error::new(std::io::ErrorKind::Other, "not from the kernel");
+        assert!(Error::try_from(&io_error).is_err());
+    }
 
-#[cfg(any(target_os = "freebsd", target_os = "macos"))]
-pub const EMPTY_TIMEX: libc::timex = libc::timex {
-    modes: 0,
-    offset: 0,
-    freq: 0,
-    maxerror: 0,
-    esterror: 0,
-    status: 0,
-    constant: 0,
-    precision: 0,
-    tolerance: 0,
-    ppsfreq: 0,
-    jitter: 0,
-    shift: 0,
-    stabil: 0,
-    jitcnt: 0,
-    calcnt: 0,
-    errcnt: 0,
-    stbcnt: 0,
-};
+    #[test]
+    fn test_unsupported_platform_and_operation_differ() {
+        // the crate knows up front that a monotonic clock cannot be steered
+        assert_eq!(
+            UnixClock::CLOCK_MONOTONIC.set_frequency(1.0),
+            Err(Error::NotSupported)
+        );
 
-impl LeapIndicator {
-    fn as_status_bit(self) -> libc::c_int {
-        match self {
-            LeapIndicator::NoWarning => 0,
-            LeapIndicator::Leap61 => libc::STA_INS,
-            LeapIndicator::Leap59 => libc::STA_DEL,
-            LeapIndicator::Unknown => libc::STA_UNSYNC,
+        // the kernel reports operations that a clock does not implement
+        assert_eq!(
+            error_from_errno(libc::EOPNOTSUPP),
+            Error::OperationUnsupported
+        );
+        assert_eq!(error_from_errno(libc::ENOTTY), Error::OperationUnsupported);
+
+        #[cfg(target_os = "linux")]
+        {
+            // /dev/null does not implement the clock device ioctls
+            let file = std::fs::File::open("/dev/null").unwrap();
+            let clock = UnixClock::from_owned_fd(file.into());
+            assert_eq!(
+                clock.capabilities().unwrap_err(),
+                Error::OperationUnsupported
+            );
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for error in [Error::NotSupported, Error::OperationUnsupported] {
+            assert_eq!(Error::ignore_not_supported(Err(error)), Ok(()));
+        }
+        assert_eq!(
+            Error::ignore_not_supported(Err(Error::Invalid)),
+            Err(Error::Invalid)
+        );
+    }
 
     #[test]
-    fn test_time_now_does_not_crash() {
-        let clock = UnixClock::CLOCK_REALTIME;
-        assert_ne!(clock.now().unwrap(), Timestamp::default(),);
+    fn test_efault_does_not_panic() {
+        assert_eq!(error_from_errno(libc::EFAULT), Error::Invalid);
     }
 
+    #[cfg(target_os = "linux")]
     #[test]
-    fn realtime_gettime() {
-        let clock = UnixClock::CLOCK_REALTIME;
-        let time = clock.clock_gettime().unwrap();
+    fn test_dropping_clock_closes_fd() {
+        // more than the usual soft limit of 1024 open files
+        for _ in 0..4096 {
+            let clock = UnixClock::open("/dev/null").unwrap();
+            drop(clock);
+        }
+    }
 
-        assert_ne!((time.tv_sec, time.tv_nsec), (0, 0))
+    // the clock is rejected before it is registered with the runtime, so no runtime is needed
+    #[cfg(all(feature = "tokio", target_os = "linux"))]
+    #[test]
+    fn test_async_clock_requires_device() {
+        let result = AsyncUnixClock::new(UnixClock::CLOCK_REALTIME);
+        assert_eq!(result.unwrap_err(), Error::Invalid);
     }
 
+    #[cfg(target_os = "linux")]
     #[test]
-    #[ignore = "requires permissions, useful for testing permissions"]
-    fn ptp0_gettime() {
-        let clock = UnixClock::CLOCK_REALTIME;
-        let time = clock.clock_gettime().unwrap();
+    fn test_clones_share_fd() {
+        let fd_is_open = |fd| unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1;
 
-        assert_ne!((time.tv_sec, time.tv_nsec), (0, 0))
+        let clock = UnixClock::open("/dev/null").unwrap();
+        let fd = clock.raw_fd().unwrap();
+
+        let clones = vec![clock.clone(), clock.clone()];
+        assert!(clones.iter().all(|clone| clone.raw_fd() == Some(fd)));
+        assert!(clones.iter().all(|clone| clone.clock == clock.clock));
+
+        drop(clock);
+        let mut clones = clones;
+        clones.pop();
+        assert!(fd_is_open(fd));
+
+        // the last clone is the only owner left, and closes the fd when dropped
+        match &clones[0].fd {
+            Some(ClockFd::Owned(fd)) => assert_eq!(Arc::strong_count(fd), 1),
+            _ => unreachable!(),
+        }
+
+        let clock = UnixClock::CLOCK_REALTIME.clone();
+        assert_eq!(clock.clock, libc::CLOCK_REALTIME);
+        assert!(clock.fd.is_none());
     }
 
+    #[cfg(target_os = "linux")]
     #[test]
-    #[ignore = "requires permissions, useful for testing permissions"]
-    fn step_clock() {
-        UnixClock::CLOCK_REALTIME
-            .step_clock(TimeOffset {
-                seconds: 0,
-                nanos: 0,
-            })
-            .unwrap();
+    fn test_clone_of_borrowed_clock_owns_a_duplicate() {
+        use std::os::unix::io::AsFd;
+
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let borrowed = UnixClock::from_borrowed_fd(file.as_fd());
+
+        let clone = borrowed.try_clone().unwrap();
+        assert_ne!(clone.raw_clock_id(), borrowed.raw_clock_id());
+        drop(borrowed);
+        drop(file);
+
+        let fd = clone.raw_fd().unwrap();
+        assert_eq!(clone.clock, clock_id_from_fd(fd));
+        assert!(unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1);
     }
 
     #[cfg(target_os = "linux")]
     #[test]
-    fn test_step_clock() {
-        let offset = TimeOffset {
-            seconds: 1,
-            nanos: 200000000,
-        };
-        let timex = UnixClock::step_clock_timex(offset);
+    fn test_borrowed_clock_keeps_fd_open() {
+        use std::os::unix::io::AsFd;
 
-        assert_eq!(timex.modes, libc::ADJ_SETOFFSET | libc::ADJ_NANO);
+        let file = std::fs::File::open("/dev/null").unwrap();
 
-        assert_eq!(timex.time.tv_sec, 1);
-        assert_eq!(timex.time.tv_usec, 200_000_000);
+        let clock = UnixClock::from_borrowed_fd(file.as_fd());
+        assert_eq!(clock.clock.clock, clock_id_from_fd(file.as_raw_fd()));
+        drop(clock);
+
+        assert_ne!(unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFD) }, -1);
     }
 
     #[test]
-    fn test_error_estimate() {
-        let est_error = Duration::from_secs_f64(0.5);
-        let max_error = Duration::from_secs_f64(1.2);
-        let timex = UnixClock::error_estimate_timex(est_error, max_error);
+    fn test_realtime_from_monotonic_pair() {
+        let pair = realtime_from_monotonic_pair().unwrap();
 
-        assert_eq!(timex.modes, libc::MOD_ESTERROR | libc::MOD_MAXERROR);
-
-        // these fields are always in microseconds
-        assert_eq!(timex.esterror, 500_000);
-        assert_eq!(timex.maxerror, 1_200_000);
+        // the system has not been up since 1970
+        assert!(pair.monotonic() < pair.realtime());
+        assert!(pair.uncertainty() < Duration::from_secs(1));
     }
 
     #[test]
@@ -812,4 +6725,116 @@ mod tests {
 
         assert_ne!(resolution, Timestamp::default());
     }
+
+    #[test]
+    fn timespec_round_trip() {
+        let timestamp = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_789,
+        };
+
+        let timespec = timestamp.as_timespec();
+        assert_eq!(
+            (timespec.tv_sec, timespec.tv_nsec),
+            (1_700_000_000, 123_456_789)
+        );
+        assert_eq!(Timestamp::from_timespec(timespec), timestamp);
+    }
+
+    #[test]
+    fn timespec_normalization() {
+        let timespec = libc::timespec {
+            tv_sec: 1,
+            tv_nsec: 1_500_000_000,
+        };
+        assert_eq!(
+            Timestamp::from_timespec(timespec),
+            Timestamp {
+                seconds: 2,
+                nanos: 500_000_000,
+            }
+        );
+
+        let timespec = libc::timespec {
+            tv_sec: 1,
+            tv_nsec: 1_000_000_000,
+        };
+        assert_eq!(
+            Timestamp::from_timespec(timespec),
+            Timestamp {
+                seconds: 2,
+                nanos: 0,
+            }
+        );
+
+        let timespec = libc::timespec {
+            tv_sec: 1,
+            tv_nsec: -250_000_000,
+        };
+        assert_eq!(
+            Timestamp::from_timespec(timespec),
+            Timestamp {
+                seconds: 0,
+                nanos: 750_000_000,
+            }
+        );
+    }
+
+    #[cfg(feature = "log")]
+    mod logging {
+        use super::*;
+        use std::{cell::RefCell, sync::Once};
+
+        thread_local! {
+            static RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Captures records per thread, so concurrently running tests don't see each other's
+        struct CaptureLogger;
+
+        impl log::Log for CaptureLogger {
+            fn enabled(&self, _metadata: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                RECORDS.with(|records| records.borrow_mut().push(record.args().to_string()));
+            }
+
+            fn flush(&self) {}
+        }
+
+        fn capture(f: impl FnOnce()) -> Vec<String> {
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                log::set_logger(&CaptureLogger).unwrap();
+                log::set_max_level(log::LevelFilter::Debug);
+            });
+
+            RECORDS.with(|records| records.borrow_mut().clear());
+            f();
+            RECORDS.with(|records| records.take())
+        }
+
+        #[test]
+        fn steering_operations_are_logged() {
+            // steering the monotonic clock fails, but the attempt is still logged
+            let records = capture(|| {
+                let clock = UnixClock::CLOCK_MONOTONIC;
+                let _ = clock.set_frequency(12.5);
+                let _ = clock.set_tai(37);
+                let _ = clock.step_clock(TimeOffset {
+                    seconds: 1,
+                    nanos: 0,
+                });
+            });
+
+            assert_eq!(records.len(), 3);
+            assert!(records[0].starts_with("set_frequency(12.5) = Err("));
+            assert!(records[1].starts_with("set_tai(37) = Err("));
+            assert!(
+                records[2].starts_with("step_clock(TimeOffset { seconds: 1, nanos: 0 }) = Err(")
+            );
+        }
+    }
 }
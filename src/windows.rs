@@ -0,0 +1,399 @@
+//! Steering the system clock on Windows.
+//!
+//! Only the system (realtime) clock can be steered, and Windows has no notion of leap seconds or
+//! the TAI offset that the kernel NTP interface on unix offers.
+use crate::{Clock, LeapIndicator, TimeOffset, Timestamp};
+use core::time::Duration;
+
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct FILETIME {
+    dwLowDateTime: u32,
+    dwHighDateTime: u32,
+}
+
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct SYSTEMTIME {
+    wYear: u16,
+    wMonth: u16,
+    wDayOfWeek: u16,
+    wDay: u16,
+    wHour: u16,
+    wMinute: u16,
+    wSecond: u16,
+    wMilliseconds: u16,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+type BOOL = i32;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetSystemTimePreciseAsFileTime(system_time_as_file_time: *mut FILETIME);
+    fn FileTimeToSystemTime(file_time: *const FILETIME, system_time: *mut SYSTEMTIME) -> BOOL;
+    fn SetSystemTime(system_time: *const SYSTEMTIME) -> BOOL;
+    fn GetSystemTimeAdjustmentPrecise(
+        time_adjustment: *mut u64,
+        time_increment: *mut u64,
+        time_adjustment_disabled: *mut BOOL,
+    ) -> BOOL;
+    fn SetSystemTimeAdjustmentPrecise(time_adjustment: u64, time_adjustment_disabled: BOOL)
+        -> BOOL;
+    fn GetLastError() -> u32;
+}
+
+const ERROR_ACCESS_DENIED: u32 = 5;
+const ERROR_NOT_SUPPORTED: u32 = 50;
+const ERROR_INVALID_PARAMETER: u32 = 87;
+const ERROR_CALL_NOT_IMPLEMENTED: u32 = 120;
+const ERROR_PRIVILEGE_NOT_HELD: u32 = 1314;
+
+/// A FILETIME counts 100ns ticks
+const TICKS_PER_SECOND: u64 = 10_000_000;
+const NANOS_PER_TICK: u32 = 100;
+/// Seconds between the FILETIME epoch (1601-01-01) and the unix epoch (1970-01-01)
+const FILETIME_UNIX_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// The Windows system clock.
+///
+/// Changing the time or frequency requires the `SE_SYSTEMTIME_NAME` privilege.
+///
+/// ```no_run
+/// use clock_steering::{Clock, windows::WindowsClock};
+///
+/// let clock = WindowsClock::SYSTEM;
+/// let now = clock.now().unwrap();
+///
+/// println!("{now:?}");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowsClock {
+    _private: (),
+}
+
+impl WindowsClock {
+    /// The system clock, which Windows keeps in UTC.
+    pub const SYSTEM: Self = Self { _private: () };
+
+    fn time_adjustment(&self) -> Result<(u64, u64, bool), Error> {
+        let mut adjustment = 0;
+        let mut increment = 0;
+        let mut disabled = 0;
+
+        // # Safety
+        //
+        // All three pointers are valid for writes for the duration of the call.
+        cerr(unsafe {
+            GetSystemTimeAdjustmentPrecise(&mut adjustment, &mut increment, &mut disabled)
+        })?;
+
+        Ok((adjustment, increment, disabled != 0))
+    }
+
+    /// Set the system time, truncated to the milliseconds of a SYSTEMTIME
+    fn set_system_time(&self, timestamp: Timestamp) -> Result<(), Error> {
+        let filetime = filetime_from_timestamp(timestamp).ok_or(Error::Invalid)?;
+        let mut system_time = SYSTEMTIME::default();
+
+        // # Safety
+        //
+        // `filetime` is valid for reads and `system_time` is valid for writes.
+        cerr(unsafe { FileTimeToSystemTime(&filetime, &mut system_time) })?;
+
+        // # Safety
+        //
+        // `system_time` was filled in by the call above and is valid for reads.
+        cerr(unsafe { SetSystemTime(&system_time) })
+    }
+}
+
+impl Clock for WindowsClock {
+    type Error = Error;
+
+    fn now(&self) -> Result<Timestamp, Self::Error> {
+        let mut filetime = FILETIME::default();
+
+        // # Safety
+        //
+        // `filetime` is valid for writes for the duration of the call.
+        unsafe { GetSystemTimePreciseAsFileTime(&mut filetime) };
+
+        Ok(timestamp_from_filetime(filetime))
+    }
+
+    fn resolution(&self) -> Result<Timestamp, Self::Error> {
+        Ok(Timestamp {
+            seconds: 0,
+            nanos: NANOS_PER_TICK,
+        })
+    }
+
+    fn get_frequency(&self) -> Result<f64, Self::Error> {
+        let (adjustment, increment, disabled) = self.time_adjustment()?;
+
+        if disabled || increment == 0 {
+            Ok(0.0)
+        } else {
+            Ok(ppm_from_adjustment(adjustment, increment))
+        }
+    }
+
+    fn set_frequency(&self, frequency: f64) -> Result<Timestamp, Self::Error> {
+        let (_, increment, _) = self.time_adjustment()?;
+        let adjustment = adjustment_from_ppm(frequency, increment);
+
+        // # Safety
+        //
+        // This call takes no pointers.
+        cerr(unsafe { SetSystemTimeAdjustmentPrecise(adjustment, 0) })?;
+
+        self.now()
+    }
+
+    /// A SYSTEMTIME only has millisecond precision, so the clock is set to the target time
+    /// truncated to whole milliseconds: a step can be off by up to a millisecond. Returns the
+    /// time read back right after the step.
+    fn step_clock(&self, offset: TimeOffset) -> Result<Timestamp, Self::Error> {
+        let now = self.now()?;
+
+        // the nanos of a `TimeOffset` are always positive, so the seconds carry the sign
+        let target = now
+            .seconds
+            .checked_add(offset.seconds)
+            .and_then(|seconds| {
+                Timestamp {
+                    seconds,
+                    nanos: now.nanos,
+                }
+                .checked_add(Duration::from_nanos(offset.nanos as u64))
+            })
+            .ok_or(Error::Invalid)?;
+
+        self.set_system_time(target)?;
+
+        self.now()
+    }
+
+    fn set_leap_seconds(&self, _leap_status: LeapIndicator) -> Result<Timestamp, Self::Error> {
+        Err(Error::NotSupported)
+    }
+
+    fn error_estimate_update(
+        &self,
+        _est_error: Duration,
+        _max_error: Duration,
+    ) -> Result<Timestamp, Self::Error> {
+        // windows has nowhere to store the error estimates
+        self.now()
+    }
+
+    fn disable_kernel_ntp_algorithm(&self) -> Result<(), Self::Error> {
+        // windows has no kernel clock discipline to disable
+        Ok(())
+    }
+
+    fn set_tai(&self, _tai_offset: i32) -> Result<(), Self::Error> {
+        Err(Error::NotSupported)
+    }
+
+    fn get_tai(&self) -> Result<i32, Self::Error> {
+        Err(Error::NotSupported)
+    }
+}
+
+fn timestamp_from_filetime(filetime: FILETIME) -> Timestamp {
+    let ticks = ((filetime.dwHighDateTime as u64) << 32) | filetime.dwLowDateTime as u64;
+
+    let seconds: i64 = (ticks / TICKS_PER_SECOND) as _;
+    let nanos: u32 = (ticks % TICKS_PER_SECOND) as _;
+
+    Timestamp {
+        seconds: (seconds - FILETIME_UNIX_EPOCH_OFFSET) as _,
+        nanos: nanos * NANOS_PER_TICK,
+    }
+}
+
+/// `None` when the timestamp is before 1601, which a FILETIME cannot represent
+fn filetime_from_timestamp(timestamp: Timestamp) -> Option<FILETIME> {
+    let seconds: i64 = timestamp.seconds as _;
+    let seconds: u64 = seconds
+        .checked_add(FILETIME_UNIX_EPOCH_OFFSET)?
+        .try_into()
+        .ok()?;
+
+    let ticks = seconds
+        .checked_mul(TICKS_PER_SECOND)?
+        .checked_add((timestamp.nanos / NANOS_PER_TICK) as u64)?;
+
+    Some(FILETIME {
+        dwLowDateTime: ticks as u32,
+        dwHighDateTime: (ticks >> 32) as u32,
+    })
+}
+
+/// The clock advances `adjustment` for every `increment` of real time
+fn ppm_from_adjustment(adjustment: u64, increment: u64) -> f64 {
+    (adjustment as f64 / increment as f64 - 1.0) * 1e6
+}
+
+fn adjustment_from_ppm(ppm: f64, increment: u64) -> u64 {
+    (increment as f64 * (1.0 + ppm * 1e-6)).round() as u64
+}
+
+/// Errors that can be thrown by modifying the Windows clock
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// Insufficient permissions to interact with the clock.
+    NoPermission,
+    /// No access to the clock.
+    NoAccess,
+    /// Invalid operation requested
+    Invalid,
+    /// Clock operation requested is not supported by operating system.
+    NotSupported,
+    /// Any other error, with the raw error code of the operating system
+    Other(i32),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Error::*;
+
+        let msg = match self {
+            Other(code) => {
+                let error = std::io::Error::from_raw_os_error(*code);
+                return write!(f, "Unexpected clock error: {error}");
+            }
+            NoPermission => "Insufficient permissions to interact with the clock.",
+            NoAccess => "No access to the clock.",
+            Invalid => "Invalid operation requested",
+            NotSupported => "Clock operation requested is not supported by operating system.",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Turn the `Error::NotSupported` error variant into `Ok(())`, to silently
+    /// ignore operations that are not supported by the current clock. All
+    /// other input values are untouched.
+    pub fn ignore_not_supported(res: Result<(), Error>) -> Result<(), Error> {
+        match res {
+            Err(Error::NotSupported) => Ok(()),
+            other => other,
+        }
+    }
+
+    fn into_raw_os_error(self) -> i32 {
+        let code = match self {
+            Self::NoPermission => ERROR_PRIVILEGE_NOT_HELD,
+            Self::NoAccess => ERROR_ACCESS_DENIED,
+            Self::Invalid => ERROR_INVALID_PARAMETER,
+            Self::NotSupported => ERROR_NOT_SUPPORTED,
+            Self::Other(code) => return code,
+        };
+
+        code as i32
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        std::io::Error::from_raw_os_error(value.into_raw_os_error())
+    }
+}
+
+fn error_from_code(code: u32) -> Error {
+    match code {
+        ERROR_PRIVILEGE_NOT_HELD => Error::NoPermission,
+        ERROR_ACCESS_DENIED => Error::NoAccess,
+        ERROR_NOT_SUPPORTED | ERROR_CALL_NOT_IMPLEMENTED => Error::NotSupported,
+        ERROR_INVALID_PARAMETER => Error::Invalid,
+        // No other errors should occur, but don't bring down the process if they do
+        other => Error::Other(other as i32),
+    }
+}
+
+fn cerr(result: BOOL) -> Result<(), Error> {
+    if result == 0 {
+        // # Safety
+        //
+        // GetLastError has no preconditions.
+        Err(error_from_code(unsafe { GetLastError() }))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filetime_epochs() {
+        // 1601-01-01, the start of the FILETIME epoch
+        assert_eq!(
+            timestamp_from_filetime(FILETIME::default()),
+            Timestamp {
+                seconds: -FILETIME_UNIX_EPOCH_OFFSET,
+                nanos: 0,
+            }
+        );
+
+        // 1970-01-01, the unix epoch
+        let ticks = 116_444_736_000_000_000u64;
+        let filetime = FILETIME {
+            dwLowDateTime: ticks as u32,
+            dwHighDateTime: (ticks >> 32) as u32,
+        };
+        assert_eq!(timestamp_from_filetime(filetime), Timestamp::default());
+    }
+
+    #[test]
+    fn filetime_round_trip() {
+        let timestamp = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_700,
+        };
+
+        let filetime = filetime_from_timestamp(timestamp).unwrap();
+        assert_eq!(timestamp_from_filetime(filetime), timestamp);
+
+        // precision below 100ns is lost
+        let timestamp = Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 123_456_789,
+        };
+
+        let filetime = filetime_from_timestamp(timestamp).unwrap();
+        assert_eq!(timestamp_from_filetime(filetime).nanos, 123_456_700);
+    }
+
+    #[test]
+    fn filetime_before_1601() {
+        let timestamp = Timestamp {
+            seconds: -FILETIME_UNIX_EPOCH_OFFSET - 1,
+            nanos: 0,
+        };
+
+        assert!(filetime_from_timestamp(timestamp).is_none());
+    }
+
+    #[test]
+    fn frequency_adjustment() {
+        let increment = 156_250;
+
+        assert_eq!(adjustment_from_ppm(0.0, increment), increment);
+        assert_eq!(adjustment_from_ppm(100.0, increment), 156_266);
+        assert_eq!(adjustment_from_ppm(-100.0, increment), 156_234);
+
+        let ppm = ppm_from_adjustment(156_266, increment);
+        assert!((ppm - 102.4).abs() < 1e-9);
+    }
+}